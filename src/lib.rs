@@ -0,0 +1,1844 @@
+//! Decoding and counting of 32-bit MIPS instruction words.
+//!
+//! `instruction_type` splits a single word into its fields, and `decode`
+//! tallies a whole program into `Stats` for the reports printed by the CLI.
+
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::io;
+use std::io::{BufRead, Read};
+use std::thread;
+
+/// How many general purpose registers there are; register fields are 5 bits,
+/// so anything above 32 can't be reached
+pub const REG_COUNT: usize = 32;
+
+/// The human (ABI) names of the registers, in order, without the `$`
+pub const REG_MAP: [&str; REG_COUNT] = [
+    "zero", "at", "v0", "v1", "a0", "a1", "a2", "a3", "t0", "t1", "t2", "t3", "t4", "t5", "t6",
+    "t7", "s0", "s1", "s2", "s3", "s4", "s5", "s6", "s7", "t8", "t9", "k0", "k1", "gp", "sp",
+    "fp", "ra",
+];
+
+/// The abi roles that `reg_group` sorts registers into
+pub const REG_GROUPS: [&str; 9] = [
+    "zero", "at", "value", "argument", "temporary", "saved", "kernel", "pointer", "return",
+];
+
+/// The r-type funcs that shift by the constant in the shamt field:
+/// sll, srl and sra (the `v` forms shift by a register instead)
+pub const SHIFT_FUNCS: [u8; 3] = [0x00, 0x02, 0x03];
+
+/// The functional categories that `category` sorts instructions into
+pub const CATEGORIES: [&str; 12] = [
+    "load", "store", "branch", "jump", "arith-imm", "arith", "shift", "mult-div", "move", "system",
+    "other", "invalid",
+];
+
+/// A decoded instruction word, split into its fields
+// the variants are named after the MIPS formats they decode
+#[allow(clippy::enum_variant_names)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InsType {
+    /// RS (bits 21-25), RT (bits 16-20), RD (bits 11-15),
+    /// SHAMT (bits 6-10), FUNC (bits 0-5); the opcode is always zero
+    RType(u8, u8, u8, u8, u8),
+    /// OP (bits 26-31), RS (bits 21-25), RT (bits 16-20), IMM (bits 0-15)
+    IType(u8, u8, u8, u16),
+    /// OP (bits 26-31), the jump target address (bits 0-25)
+    JType(u8, u32),
+    /// a coprocessor operation (opcodes 0x10-0x13): the coprocessor number
+    /// (bits 26-27), RS (bits 21-25, which picks the operation), RT
+    /// (bits 16-20), RD (bits 11-15) and whatever is left (bits 0-10)
+    Coprocessor(u8, u8, u8, u8, u16),
+    /// the whole word, when its opcode (or r-type func, or regimm rt)
+    /// doesn't belong to any real MIPS instruction
+    Invalid(u32),
+}
+
+/// The counts gathered over a run of instructions
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stats {
+    /// set before recording to leave nops out of every count but `nops`
+    pub exclude_nops: bool,
+    /// set before recording to fill in `word_counts`, which holds every
+    /// different word and so can grow with the input
+    pub count_words: bool,
+    /// how many nops (the all zero word, `sll $0, $0, 0`) were seen
+    pub nops: usize,
+    /// how many instructions were counted
+    pub total: usize,
+    pub r_type: usize,
+    pub i_type: usize,
+    pub j_type: usize,
+    /// coprocessor operations, see `InsType::Coprocessor`
+    pub coprocessor: usize,
+    /// words that aren't any real instruction, see `InsType::Invalid`
+    pub invalid: usize,
+    /// i-type, j-type and coprocessor instructions per opcode, r-types are
+    /// in `functions`
+    pub opcodes: [usize; 0x40],
+    /// every word per opcode field (r-types are all opcode 0), split up by
+    /// how it decoded: r-type, i-type, j-type, coprocessor, invalid
+    pub opcode_classes: [[usize; 5]; 0x40],
+    /// r-type instructions per function code
+    pub functions: [usize; 0x40],
+    /// per register, how many times an r-type used it as rs, rt or rd
+    pub reg_r_type: [usize; REG_COUNT],
+    /// per register, how many times an i-type used it as rs or rt (regimm's
+    /// rt is left out, it picks the instruction instead)
+    pub reg_i_type: [usize; REG_COUNT],
+    /// the smallest and largest i-type immediates, unsigned
+    pub imm_min: u16,
+    pub imm_max: u16,
+    /// how many i-type immediates were zero
+    pub imm_zero: usize,
+    /// how many i-type immediates would be negative when sign extended
+    pub imm_negative: usize,
+    /// per opcode, the sum of every i-type immediate, sign extended except
+    /// for the logical ones and lui; see `mean_imm`
+    pub imm_sums: [i64; 0x40],
+    /// instructions per functional category, in the order of `CATEGORIES`
+    pub categories: [usize; 12],
+    /// constant shifts (see `SHIFT_FUNCS`) per shift amount
+    pub shamts: [usize; 32],
+    /// per register, how many times an r-type read it as rs or rt
+    pub reg_r_read: [usize; REG_COUNT],
+    /// per register, how many times an r-type wrote it as rd; the ones like
+    /// `jr`, `mult` and `syscall` whose rd is only a zero field don't count
+    pub reg_r_write: [usize; REG_COUNT],
+    /// how many i-types used each (rs, rt) pair of registers, regimm aside
+    pub reg_pairs: HashMap<(u8, u8), usize>,
+    /// per register, how many times an r-type or i-type read it
+    pub reg_sources: [usize; REG_COUNT],
+    /// per register, how many times an r-type or i-type wrote it
+    pub reg_dests: [usize; REG_COUNT],
+    /// load and store offsets that are multiples of 4, of only 2, and odd
+    pub offset_alignment: [usize; 3],
+    /// word loads and stores (not lwl/lwr and co.) with an offset that
+    /// isn't a multiple of 4, which fault unless the base makes up for it
+    pub misaligned_words: usize,
+    /// branches with an offset of zero or more
+    pub branches_forward: usize,
+    /// branches with a negative offset
+    pub branches_backward: usize,
+    /// how many `jr $ra` there were, about one per function
+    pub returns: usize,
+    /// how many `syscall` (func 0x0C) and `break` (func 0x0D) there were,
+    /// the places a program hands over to the os
+    pub syscalls: usize,
+    pub breaks: usize,
+    /// every different word seen, with how many times it was; empty
+    /// unless `count_words` was set
+    pub word_counts: HashMap<u32, usize>,
+    /// what fills each branch or jump delay slot, by class like `opcode_classes`
+    pub delay_slots: [usize; 5],
+    /// how many of those delay slots hold a nop
+    pub delay_slot_nops: usize,
+    /// whether the next instruction recorded is in a delay slot
+    pub in_delay_slot: bool,
+}
+
+impl Default for Stats {
+    fn default() -> Stats {
+        Stats {
+            exclude_nops: false,
+            count_words: false,
+            nops: 0,
+            total: 0,
+            r_type: 0,
+            i_type: 0,
+            j_type: 0,
+            coprocessor: 0,
+            invalid: 0,
+            opcodes: [0; 0x40], // 0x40 zeroes, opcode is a full 6 bits
+            opcode_classes: [[0; 5]; 0x40],
+            functions: [0; 0x40], // 0x40 zeroes, func is a full 6 bits
+            reg_r_type: [0; REG_COUNT], // all zeros
+            reg_i_type: [0; REG_COUNT], // all zeros
+            imm_min: u16::MAX,
+            imm_max: 0,
+            imm_zero: 0,
+            imm_negative: 0,
+            imm_sums: [0; 0x40],
+            categories: [0; 12],
+            shamts: [0; 32],
+            reg_r_read: [0; REG_COUNT],
+            reg_r_write: [0; REG_COUNT],
+            reg_pairs: HashMap::new(),
+            reg_sources: [0; REG_COUNT],
+            reg_dests: [0; REG_COUNT],
+            offset_alignment: [0; 3],
+            misaligned_words: 0,
+            branches_forward: 0,
+            branches_backward: 0,
+            returns: 0,
+            syscalls: 0,
+            breaks: 0,
+            word_counts: HashMap::new(),
+            delay_slots: [0; 5],
+            delay_slot_nops: 0,
+            in_delay_slot: false,
+        }
+    }
+}
+
+impl Stats {
+    /// the average immediate of the i-types with this opcode, None if there
+    /// weren't any
+    pub fn mean_imm(&self, op: u8) -> Option<f64> {
+        // only i-types have an immediate, so only they are counted
+        let count = self.opcode_classes.get(op as usize)?[1];
+        if count == 0 {
+            return None;
+        }
+        Some(self.imm_sums[op as usize] as f64 / count as f64)
+    }
+
+    /// counts every word like `record` does, but split up over this many
+    /// threads, for inputs big enough that counting is the slow part
+    pub fn record_parallel(&mut self, instructions: &[u32], threads: usize) {
+        let chunk_size = instructions.len().div_ceil(threads.max(1)).max(1);
+        let chunks: Vec<&[u32]> = instructions.chunks(chunk_size).collect();
+        let (exclude_nops, count_words) = (self.exclude_nops, self.count_words);
+        let in_delay_slot = self.in_delay_slot;
+
+        let parts: Vec<Stats> = thread::scope(|scope| {
+            let handles: Vec<_> = chunks
+                .iter()
+                .enumerate()
+                .map(|(i, chunk)| {
+                    // a chunk after a branch or jump starts in its delay slot
+                    let in_delay_slot = match i.checked_sub(1) {
+                        Some(prev) => chunks[prev]
+                            .last()
+                            .is_some_and(|word| has_delay_slot(category(&instruction_type(*word)))),
+                        None => in_delay_slot,
+                    };
+                    scope.spawn(move || {
+                        let mut stats = Stats {
+                            exclude_nops,
+                            count_words,
+                            in_delay_slot,
+                            ..Stats::default()
+                        };
+                        for instruction in *chunk {
+                            stats.record(*instruction);
+                        }
+                        stats
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().expect("a counting thread panicked"))
+                .collect()
+        });
+        // in order, so the delay slot state ends up that of the last chunk
+        for part in &parts {
+            self.merge(part);
+        }
+    }
+
+    /// adds the counts of `other` into these, as if its instructions had been
+    /// recorded right after; every tally is a sum except the immediate
+    /// min and max
+    pub fn merge(&mut self, other: &Stats) {
+        fn add(into: &mut [usize], from: &[usize]) {
+            for (into, from) in into.iter_mut().zip(from) {
+                *into += from;
+            }
+        }
+
+        self.nops += other.nops;
+        self.total += other.total;
+        self.r_type += other.r_type;
+        self.i_type += other.i_type;
+        self.j_type += other.j_type;
+        self.coprocessor += other.coprocessor;
+        self.invalid += other.invalid;
+        add(&mut self.opcodes, &other.opcodes);
+        for (into, from) in self.opcode_classes.iter_mut().zip(&other.opcode_classes) {
+            add(into, from);
+        }
+        add(&mut self.functions, &other.functions);
+        add(&mut self.reg_r_type, &other.reg_r_type);
+        add(&mut self.reg_i_type, &other.reg_i_type);
+        self.imm_min = self.imm_min.min(other.imm_min);
+        self.imm_max = self.imm_max.max(other.imm_max);
+        self.imm_zero += other.imm_zero;
+        self.imm_negative += other.imm_negative;
+        for (into, from) in self.imm_sums.iter_mut().zip(&other.imm_sums) {
+            *into += from;
+        }
+        add(&mut self.categories, &other.categories);
+        add(&mut self.shamts, &other.shamts);
+        add(&mut self.reg_r_read, &other.reg_r_read);
+        add(&mut self.reg_r_write, &other.reg_r_write);
+        for (pair, count) in &other.reg_pairs {
+            *self.reg_pairs.entry(*pair).or_insert(0) += count;
+        }
+        add(&mut self.reg_sources, &other.reg_sources);
+        add(&mut self.reg_dests, &other.reg_dests);
+        add(&mut self.offset_alignment, &other.offset_alignment);
+        self.misaligned_words += other.misaligned_words;
+        self.branches_forward += other.branches_forward;
+        self.branches_backward += other.branches_backward;
+        self.returns += other.returns;
+        self.syscalls += other.syscalls;
+        self.breaks += other.breaks;
+        for (word, count) in &other.word_counts {
+            *self.word_counts.entry(*word).or_insert(0) += count;
+        }
+        add(&mut self.delay_slots, &other.delay_slots);
+        self.delay_slot_nops += other.delay_slot_nops;
+        // whatever comes next follows the last of other's instructions
+        self.in_delay_slot = other.in_delay_slot;
+    }
+
+    /// counts one more instruction into every tally
+    pub fn record(&mut self, instruction: u32) {
+        let decoded = instruction_type(instruction);
+        let class = match decoded {
+            InsType::RType(..) => 0,
+            InsType::IType(..) => 1,
+            InsType::JType(..) => 2,
+            InsType::Coprocessor(..) => 3,
+            InsType::Invalid(_) => 4,
+        };
+
+        // the instruction right after a branch or jump runs before it lands,
+        // nops there are counted even when left out of everything else
+        if self.in_delay_slot {
+            self.delay_slots[class] += 1;
+            if instruction == 0 {
+                self.delay_slot_nops += 1;
+            }
+        }
+        let category = category(&decoded);
+        self.in_delay_slot = has_delay_slot(category);
+
+        if instruction == 0 {
+            self.nops += 1;
+            if self.exclude_nops {
+                // padding, which would otherwise pile up on sll and $zero
+                return;
+            }
+        }
+        self.total += 1;
+        if self.count_words {
+            *self.word_counts.entry(instruction).or_insert(0) += 1;
+        }
+
+        self.opcode_classes[(instruction >> 26) as usize][class] += 1;
+        self.categories[category] += 1;
+        let (sources, dest) = operand_roles(&decoded);
+        for source in sources.iter().flatten() {
+            tally(&mut self.reg_sources, *source);
+        }
+        if let Some(dest) = dest {
+            tally(&mut self.reg_dests, dest);
+        }
+
+        match decoded {
+            // Matching on r-type means checking for rs, rt, rd
+            InsType::RType(rs, rt, rd, shamt, func) => {
+                self.r_type += 1;
+                self.functions[func as usize] += 1;
+                if SHIFT_FUNCS.contains(&func) {
+                    self.shamts[shamt as usize] += 1;
+                }
+                tally(&mut self.reg_r_type, rs);
+                tally(&mut self.reg_r_type, rt);
+                tally(&mut self.reg_r_type, rd);
+                // rs and rt are the sources, rd is the destination
+                tally(&mut self.reg_r_read, rs);
+                tally(&mut self.reg_r_read, rt);
+                if dest == Some(rd) {
+                    tally(&mut self.reg_r_write, rd);
+                }
+                match func {
+                    0x08 if rs == 31 => self.returns += 1,
+                    0x0C => self.syscalls += 1,
+                    0x0D => self.breaks += 1,
+                    _ => {}
+                }
+            }
+            // Matching on i-type means only checking rs, rt
+            InsType::IType(op, rs, rt, imm) => {
+                self.i_type += 1;
+                self.imm_min = self.imm_min.min(imm);
+                self.imm_max = self.imm_max.max(imm);
+                if imm == 0 {
+                    self.imm_zero += 1;
+                }
+                if imm & 0x8000 != 0 {
+                    self.imm_negative += 1;
+                }
+                self.opcodes[op as usize] += 1;
+                // andi, ori, xori and lui take their immediate as is
+                self.imm_sums[op as usize] += if (0x0C..=0x0F).contains(&op) {
+                    imm as i64
+                } else {
+                    imm as i16 as i64
+                };
+                tally(&mut self.reg_i_type, rs);
+                // regimm's rt picks the instruction (bltz, bgez, ...), it isn't a register
+                if op != 0x01 {
+                    tally(&mut self.reg_i_type, rt);
+                    *self.reg_pairs.entry((rs, rt)).or_insert(0) += 1;
+                }
+
+                if CATEGORIES[category] == "branch" {
+                    // a negative offset goes back, usually to the top of a loop
+                    if (imm as i16) < 0 {
+                        self.branches_backward += 1;
+                    } else {
+                        self.branches_forward += 1;
+                    }
+                }
+
+                if CATEGORIES[category] == "load" || CATEGORIES[category] == "store" {
+                    // the offset is sign extended, so go by the signed value
+                    let offset = imm as i16;
+                    let alignment = if offset % 4 == 0 {
+                        0
+                    } else if offset % 2 == 0 {
+                        1
+                    } else {
+                        2
+                    };
+                    self.offset_alignment[alignment] += 1;
+                    // lw, sw, ll, sc and the coprocessor word loads/stores
+                    let word_sized =
+                        matches!(op, 0x23 | 0x2B | 0x30 | 0x38 | 0x31 | 0x32 | 0x39 | 0x3A);
+                    if word_sized && alignment != 0 {
+                        self.misaligned_words += 1;
+                    }
+                }
+            }
+            // No registers were harmed in these jump instructions
+            InsType::JType(op, _) => {
+                self.j_type += 1;
+                self.opcodes[op as usize] += 1;
+            }
+            // these have an opcode, but their registers aren't all general
+            // purpose ones so none are counted
+            InsType::Coprocessor(z, _, _, _, _) => {
+                self.coprocessor += 1;
+                self.opcodes[0x10 + z as usize] += 1;
+            }
+            // the opcode or func that made it invalid is still tallied,
+            // but the other fields mean nothing so no registers are
+            InsType::Invalid(word) => {
+                self.invalid += 1;
+                match word >> 26 {
+                    0 => self.functions[(word & 0x3F) as usize] += 1,
+                    op => self.opcodes[op as usize] += 1,
+                }
+            }
+        }
+    }
+}
+
+/// returns the shannon entropy, in bits, of a distribution given as counts;
+/// 0 when everything is in one bucket (or there is nothing at all)
+pub fn entropy(counts: &[usize]) -> f64 {
+    let total: usize = counts.iter().sum();
+    counts
+        .iter()
+        .filter(|count| **count > 0)
+        .map(|count| {
+            let p = *count as f64 / total as f64;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+/// counts one more use of a register, ignoring any past `REG_COUNT` so a
+/// smaller register file can't be indexed out of bounds
+fn tally(counts: &mut [usize; REG_COUNT], reg: u8) {
+    if let Some(count) = counts.get_mut(reg as usize) {
+        *count += 1;
+    }
+}
+
+/// whether an instruction of this category is followed by a delay slot
+fn has_delay_slot(category: usize) -> bool {
+    CATEGORIES[category] == "branch" || CATEGORIES[category] == "jump"
+}
+
+/// counts up the statistics of every instruction in the slice
+pub fn decode(instructions: &[u32]) -> Stats {
+    let mut stats = Stats::default();
+    for instruction in instructions {
+        stats.record(*instruction);
+    }
+    stats
+}
+
+/// decodes each word in turn and hands it to `f` with its index, the raw
+/// word and what it decoded to, for analyses that `Stats` doesn't have
+pub fn for_each_instruction<F: FnMut(usize, u32, InsType)>(words: &[u32], mut f: F) {
+    for (idx, word) in words.iter().enumerate() {
+        f(idx, *word, instruction_type(*word));
+    }
+}
+
+/// counts up the statistics of instructions as they are read, so a huge
+/// input never has to be held in memory all at once
+pub fn decode_stream(
+    instructions: impl Iterator<Item = Result<u32, DecodeError>>,
+) -> Result<Stats, DecodeError> {
+    let mut stats = Stats::default();
+    for instruction in instructions {
+        stats.record(instruction?);
+    }
+    Ok(stats)
+}
+
+/// An iterator over the instructions of some text input, one per line,
+/// made by `read_instructions`
+pub struct InstructionReader<R> {
+    reader: R,
+    binary_input: bool,
+    input: String,      // mutable buffer
+    line_number: usize, // 1-based, for error messages
+    done: bool,
+    // set when a malformed line, rather than EOF, ended the input
+    stopped_early: bool,
+    // split lines on whitespace and commas, see `split_words`
+    split_words: bool,
+    // words are decimal numbers, see `decimal`
+    decimal: bool,
+    // the words of the current line that haven't been handed out yet
+    pending: VecDeque<Result<u32, DecodeError>>,
+}
+
+/// reads one instruction per line, as hex (the `0x` is optional) or as binary digits,
+/// skipping blank lines and lines starting with `#`, trimming spaces and tabs
+/// around each word, and stopping at the first other line that doesn't look
+/// like an instruction;
+/// a line of the right shape that fails to parse is a `DecodeError::BadHex`
+pub fn read_instructions<R: BufRead>(reader: R, binary_input: bool) -> InstructionReader<R> {
+    InstructionReader {
+        reader,
+        binary_input,
+        input: String::new(),
+        line_number: 0,
+        done: false,
+        stopped_early: false,
+        split_words: false,
+        decimal: false,
+        pending: VecDeque::new(),
+    }
+}
+
+/// reads every instruction into a vector, see `read_instructions`
+pub fn parse_instructions(
+    reader: impl BufRead,
+    binary_input: bool,
+) -> Result<Vec<u32>, DecodeError> {
+    read_instructions(reader, binary_input).collect()
+}
+
+impl<R> InstructionReader<R> {
+    /// reads every word of a line separated by whitespace or commas, like
+    /// `0x21280004, 0x8fa40008`, instead of a single word per line
+    pub fn split_words(mut self) -> Self {
+        self.split_words = true;
+        self
+    }
+
+    /// reads each word as a plain decimal number, like simulators print them,
+    /// instead of hex or binary; one too big for 32 bits is a `BadHex` error
+    pub fn decimal(mut self) -> Self {
+        self.decimal = true;
+        self
+    }
+
+    /// the line number and text of the line that ended the input early, if
+    /// reading stopped at a line that wasn't an instruction instead of EOF
+    pub fn stopped_at(&self) -> Option<(usize, &str)> {
+        if self.stopped_early {
+            Some((self.line_number, self.input.trim()))
+        } else {
+            None
+        }
+    }
+}
+
+/// what one word of text turned out to be
+enum Token {
+    Word(u32),
+    // too long or empty, so not an instruction at all
+    WrongShape,
+    // the right length, but not digits
+    Unparsable,
+}
+
+/// parses one word of text as hex (the `0x` is optional), binary or decimal digits
+fn parse_token(token: &str, binary_input: bool, decimal: bool) -> Token {
+    if decimal {
+        // decimal words have no set length, so only digits make one
+        if token.is_empty() || !token.bytes().all(|b| b.is_ascii_digit()) {
+            return Token::WrongShape;
+        }
+        // all digits but past u32::MAX can't be a word
+        return match token.parse() {
+            Ok(instruction) => Token::Word(instruction),
+            Err(_) => Token::Unparsable,
+        };
+    }
+
+    let (digit_str, radix, max_len) = if binary_input {
+        // binary words can be any length up to 32 digits
+        (token, 2, 32)
+    } else {
+        // the '0x' is optional, dumps from objdump and the like leave it off
+        let digit_str = token
+            .strip_prefix("0x")
+            .or_else(|| token.strip_prefix("0X"))
+            .unwrap_or(token);
+        (digit_str, 16, 8)
+    };
+
+    if digit_str.is_empty() || digit_str.len() > max_len {
+        // if we dont get what we expect, we are done
+        return Token::WrongShape;
+    }
+    match u32::from_str_radix(digit_str, radix) {
+        Ok(instruction) => Token::Word(instruction),
+        Err(_) => Token::Unparsable,
+    }
+}
+
+impl<R: BufRead> Iterator for InstructionReader<R> {
+    type Item = Result<u32, DecodeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            // hand out what is left of the last line first
+            if let Some(word) = self.pending.pop_front() {
+                return Some(word);
+            }
+            if self.done {
+                return None;
+            }
+
+            // clean out buffer for the next line of text
+            self.input.clear();
+
+            // read in a line from the input
+            let bytes = match self.reader.read_line(&mut self.input) {
+                Ok(bytes) => bytes,
+                Err(err) => {
+                    self.done = true;
+                    return Some(Err(DecodeError::Io(err)));
+                }
+            };
+            self.line_number += 1;
+
+            // some editors start a utf-8 file with a byte order mark, which
+            // would otherwise make the first line look like garbage
+            if self.line_number == 1 && self.input.starts_with('\u{feff}') {
+                self.input.drain(..'\u{feff}'.len_utf8());
+            }
+
+            if bytes == 0 {
+                // a genuine EOF, everything was read
+                self.done = true;
+                return None;
+            }
+
+            // blank lines and # comments are skipped, annotated dumps have plenty
+            let text = self.input.trim();
+            if text.is_empty() || text.starts_with('#') {
+                continue;
+            }
+
+            // the line ending (\n, or \r\n from windows) and any spaces or tabs
+            // padding the word are chopped off, but a line with anything else
+            // on it still doesn't look like an instruction
+            let line = text;
+            let tokens: Vec<&str> = if self.split_words {
+                line.split(|c: char| c.is_whitespace() || c == ',')
+                    .filter(|token| !token.is_empty())
+                    .collect()
+            } else {
+                vec![line]
+            };
+
+            for token in tokens {
+                match parse_token(token, self.binary_input, self.decimal) {
+                    Token::Word(instruction) => self.pending.push_back(Ok(instruction)),
+                    Token::WrongShape => {
+                        // bad string... we can stop here
+                        self.done = true;
+                        self.stopped_early = true;
+                        break;
+                    }
+                    Token::Unparsable => {
+                        // a bad line ends the input, the error says where it was
+                        self.done = true;
+                        self.pending.push_back(Err(bad_line(self.line_number, &self.input)));
+                        break;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// An iterator over the instructions of raw binary input, made by `read_raw`
+pub struct RawReader<R> {
+    reader: R,
+    big_endian: bool,
+    done: bool,
+}
+
+/// reads 4-byte instruction words straight from the input, assembling each
+/// in big or little endian order; a trailing partial word is an error
+pub fn read_raw<R: Read>(reader: R, big_endian: bool) -> RawReader<R> {
+    RawReader {
+        reader,
+        big_endian,
+        done: false,
+    }
+}
+
+/// splits raw memory into instruction words, in big or little endian order;
+/// a length that isn't a multiple of 4 is a `DecodeError::ShortWord`
+pub fn decode_words(bytes: &[u8], big_endian: bool) -> Result<Vec<u32>, DecodeError> {
+    read_raw(bytes, big_endian).collect()
+}
+
+impl<R: Read> Iterator for RawReader<R> {
+    type Item = Result<u32, DecodeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let mut bytes = [0; 4];
+        let mut filled = 0;
+        while filled < 4 {
+            // a read can come back with less than asked for, so keep going
+            match self.reader.read(&mut bytes[filled..]) {
+                Ok(0) => break, // EOF
+                Ok(n) => filled += n,
+                Err(ref err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => {
+                    self.done = true;
+                    return Some(Err(DecodeError::Io(err)));
+                }
+            }
+        }
+
+        if filled < 4 {
+            self.done = true;
+            if filled == 0 {
+                // the input ended right on a word boundary
+                return None;
+            }
+            return Some(Err(DecodeError::ShortWord { bytes: filled }));
+        }
+
+        Some(Ok(if self.big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        }))
+    }
+}
+
+/// Everything that can go wrong while reading instructions
+#[derive(Debug)]
+pub enum DecodeError {
+    /// the input itself couldn't be read
+    Io(io::Error),
+    /// a line had the shape of an instruction but not valid digits (hex, or
+    /// binary or decimal ones when reading those), or a decimal word was too
+    /// big for 32 bits; `line` is 1-based and `text` has no line ending
+    BadHex { line: usize, text: String },
+    /// raw input ended partway through a word, after this many of its 4 bytes
+    ShortWord { bytes: usize },
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DecodeError::Io(err) => write!(f, "{}", err),
+            DecodeError::BadHex { line, text } => {
+                write!(f, "line {}: could not parse {:?} as an instruction", line, text)
+            }
+            DecodeError::ShortWord { bytes } => {
+                write!(f, "input ends with a partial word of {} bytes", bytes)
+            }
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DecodeError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for DecodeError {
+    fn from(err: io::Error) -> Self {
+        DecodeError::Io(err)
+    }
+}
+
+/// builds the error for a line that could not be parsed as an instruction
+fn bad_line(line_number: usize, line: &str) -> DecodeError {
+    DecodeError::BadHex {
+        line: line_number,
+        text: line.trim().to_string(),
+    }
+}
+
+/// returns the enum representation of the 32-bit mips instruction
+pub fn instruction_type(instruction: u32) -> InsType {
+    // the top 6 bits are the opcode, which alone decides the format
+    let opcode = (instruction >> 26) as u8;
+
+    match opcode {
+        // 000010 (j) and 000011 (jal) are the only J-types
+        0x02 | 0x03 => {
+            // the jump target is the low 26 bits of the word
+            InsType::JType(opcode, instruction & 0x03FF_FFFF)
+        }
+        // An opcode of all zeroes means R-type, if the func is a real one
+        0x00 if func_name((instruction & 0x3F) as u8).is_none() => {
+            InsType::Invalid(instruction)
+        }
+        0x00 => InsType::RType(
+            ((instruction >> 21) & 0x1F) as u8,
+            ((instruction >> 16) & 0x1F) as u8,
+            ((instruction >> 11) & 0x1F) as u8,
+            ((instruction >> 6) & 0x1F) as u8,
+            (instruction & 0x3F) as u8,
+        ),
+        // coprocessors 0 through 3 have their own encoding
+        0x10..=0x13 => InsType::Coprocessor(
+            opcode & 0x03,
+            ((instruction >> 21) & 0x1F) as u8,
+            ((instruction >> 16) & 0x1F) as u8,
+            ((instruction >> 11) & 0x1F) as u8,
+            (instruction & 0x07FF) as u16,
+        ),
+        // regimm picks the instruction with its rt field
+        0x01 if regimm_name(((instruction >> 16) & 0x1F) as u8).is_none() => {
+            InsType::Invalid(instruction)
+        }
+        // no instruction uses these opcodes at all
+        _ if opcode != 0x01 && opcode_name(opcode).is_none() => InsType::Invalid(instruction),
+        // All other cases are I-Types
+        _ => InsType::IType(
+            opcode,
+            ((instruction >> 21) & 0x1F) as u8,
+            ((instruction >> 16) & 0x1F) as u8,
+            (instruction & 0xFFFF) as u16,
+        ),
+    }
+}
+
+/// packs a decoded instruction back into its 32-bit word, the inverse of
+/// `instruction_type`
+pub fn encode(instruction: InsType) -> u32 {
+    match instruction {
+        InsType::RType(rs, rt, rd, shamt, func) => {
+            (rs as u32 & 0x1F) << 21
+                | (rt as u32 & 0x1F) << 16
+                | (rd as u32 & 0x1F) << 11
+                | (shamt as u32 & 0x1F) << 6
+                | (func as u32 & 0x3F)
+        }
+        InsType::IType(op, rs, rt, imm) => {
+            (op as u32 & 0x3F) << 26
+                | (rs as u32 & 0x1F) << 21
+                | (rt as u32 & 0x1F) << 16
+                | imm as u32
+        }
+        InsType::JType(op, addr) => (op as u32 & 0x3F) << 26 | (addr & 0x03FF_FFFF),
+        // the opcode is 0100zz for coprocessor z
+        InsType::Coprocessor(z, rs, rt, rd, rest) => {
+            (0x10 | (z as u32 & 0x03)) << 26
+                | (rs as u32 & 0x1F) << 21
+                | (rt as u32 & 0x1F) << 16
+                | (rd as u32 & 0x1F) << 11
+                | (rest as u32 & 0x07FF)
+        }
+        // nothing was pulled apart, so the word goes back as it was
+        InsType::Invalid(word) => word,
+    }
+}
+
+/// returns the mnemonic of an r-type function code, if it is a defined one
+pub fn func_name(func: u8) -> Option<&'static str> {
+    let name = match func {
+        0x00 => "sll",
+        0x02 => "srl",
+        0x03 => "sra",
+        0x04 => "sllv",
+        0x06 => "srlv",
+        0x07 => "srav",
+        0x08 => "jr",
+        0x09 => "jalr",
+        0x0A => "movz",
+        0x0B => "movn",
+        0x0C => "syscall",
+        0x0D => "break",
+        0x0F => "sync",
+        0x10 => "mfhi",
+        0x11 => "mthi",
+        0x12 => "mflo",
+        0x13 => "mtlo",
+        0x18 => "mult",
+        0x19 => "multu",
+        0x1A => "div",
+        0x1B => "divu",
+        0x20 => "add",
+        0x21 => "addu",
+        0x22 => "sub",
+        0x23 => "subu",
+        0x24 => "and",
+        0x25 => "or",
+        0x26 => "xor",
+        0x27 => "nor",
+        0x2A => "slt",
+        0x2B => "sltu",
+        0x30 => "tge",
+        0x31 => "tgeu",
+        0x32 => "tlt",
+        0x33 => "tltu",
+        0x34 => "teq",
+        0x36 => "tne",
+        _ => return None,
+    };
+    Some(name)
+}
+
+/// returns the abi role of a register, as an index into `REG_GROUPS`
+pub fn reg_group(idx: usize) -> usize {
+    let name = match REG_MAP[idx] {
+        "gp" | "sp" | "fp" => "pointer",
+        "ra" => "return",
+        // the rest go by the letter in front of their number
+        reg => match &reg[..1] {
+            "v" => "value",
+            "a" if reg != "at" => "argument",
+            "t" => "temporary",
+            "s" => "saved",
+            "k" => "kernel",
+            _ => reg,
+        },
+    };
+    REG_GROUPS.iter().position(|group| *group == name).unwrap()
+}
+
+/// returns the general purpose registers an instruction reads (up to two)
+/// and the one it writes, if any; registers only written implicitly, like
+/// `$ra` by `jal`, and coprocessor registers aren't included
+pub fn operand_roles(instruction: &InsType) -> ([Option<u8>; 2], Option<u8>) {
+    match *instruction {
+        InsType::RType(rs, rt, rd, _, func) => match func {
+            // shifts by a constant only read rt
+            0x00 | 0x02 | 0x03 => ([Some(rt), None], Some(rd)),
+            0x08 => ([Some(rs), None], None),
+            0x09 => ([Some(rs), None], Some(rd)),
+            // syscall, break and sync have no registers
+            0x0C | 0x0D | 0x0F => ([None, None], None),
+            // mfhi and mflo write rd, mthi and mtlo read rs
+            0x10 | 0x12 => ([None, None], Some(rd)),
+            0x11 | 0x13 => ([Some(rs), None], None),
+            // multiply, divide and traps write hi/lo or nothing at all
+            0x18..=0x1B | 0x30..=0x36 => ([Some(rs), Some(rt)], None),
+            _ => ([Some(rs), Some(rt)], Some(rd)),
+        },
+        InsType::IType(op, rs, rt, _) => match op {
+            // regimm and the branches against zero compare only rs
+            0x01 | 0x06 | 0x07 | 0x16 | 0x17 => ([Some(rs), None], None),
+            0x04 | 0x05 | 0x14 | 0x15 => ([Some(rs), Some(rt)], None),
+            0x0F => ([None, None], Some(rt)),
+            // loads write rt from an address based on rs
+            0x20..=0x26 | 0x30 => ([Some(rs), None], Some(rt)),
+            // stores read both, sc also writes whether it worked into rt
+            0x28..=0x2E => ([Some(rs), Some(rt)], None),
+            0x38 => ([Some(rs), Some(rt)], Some(rt)),
+            // coprocessor loads/stores, cache and pref only have a base register
+            0x2F | 0x31..=0x3F => ([Some(rs), None], None),
+            // everything else is register-immediate: rt = rs op imm
+            _ => ([Some(rs), None], Some(rt)),
+        },
+        InsType::JType(..) | InsType::Coprocessor(..) | InsType::Invalid(_) => ([None, None], None),
+    }
+}
+
+/// returns the functional category of an instruction, as an index into
+/// `CATEGORIES`: loads, stores, branches, arithmetic and so on
+pub fn category(instruction: &InsType) -> usize {
+    let name = match *instruction {
+        InsType::RType(_, _, _, _, func) => match func {
+            0x00..=0x07 => "shift",
+            0x08 | 0x09 => "jump",
+            // syscall, break, sync and the traps
+            0x0C | 0x0D | 0x0F | 0x30..=0x36 => "system",
+            // conditional moves and moves to or from hi/lo
+            0x0A | 0x0B | 0x10..=0x13 => "move",
+            0x18..=0x1B => "mult-div",
+            _ => "arith",
+        },
+        InsType::IType(op, _, _, _) => match op {
+            0x01 | 0x04..=0x07 | 0x14..=0x17 => "branch",
+            0x08..=0x0F => "arith-imm",
+            0x20..=0x26 | 0x30..=0x32 | 0x35 | 0x36 => "load",
+            0x28..=0x2B | 0x2E | 0x38..=0x3A | 0x3D | 0x3E => "store",
+            // coprocessor operations, cache and pref
+            _ => "other",
+        },
+        InsType::JType(_, _) => "jump",
+        InsType::Coprocessor(..) => "other",
+        InsType::Invalid(_) => "invalid",
+    };
+    // every name above is one of the categories
+    CATEGORIES.iter().position(|category| *category == name).unwrap_or(10)
+}
+
+/// returns the class ("R", "I", "J" or "C" for coprocessor) that every
+/// instruction with this opcode should decode to, or None if no
+/// instruction uses the opcode
+pub fn opcode_class(op: u8) -> Option<&'static str> {
+    match op {
+        0x00 => Some("R"),
+        0x02 | 0x03 => Some("J"),
+        0x10..=0x13 => Some("C"),
+        // regimm has no single name, but is always an i-type
+        0x01 => Some("I"),
+        _ => opcode_name(op).map(|_| "I"),
+    }
+}
+
+/// returns the mnemonic of an opcode, if it is a defined one; the r-type
+/// (0x00) and regimm (0x01) opcodes are named by another field instead
+pub fn opcode_name(op: u8) -> Option<&'static str> {
+    let name = match op {
+        0x02 => "j",
+        0x03 => "jal",
+        0x04 => "beq",
+        0x05 => "bne",
+        0x06 => "blez",
+        0x07 => "bgtz",
+        0x08 => "addi",
+        0x09 => "addiu",
+        0x0A => "slti",
+        0x0B => "sltiu",
+        0x0C => "andi",
+        0x0D => "ori",
+        0x0E => "xori",
+        0x0F => "lui",
+        0x10 => "cop0",
+        0x11 => "cop1",
+        0x12 => "cop2",
+        0x13 => "cop3",
+        0x14 => "beql",
+        0x15 => "bnel",
+        0x16 => "blezl",
+        0x17 => "bgtzl",
+        0x20 => "lb",
+        0x21 => "lh",
+        0x22 => "lwl",
+        0x23 => "lw",
+        0x24 => "lbu",
+        0x25 => "lhu",
+        0x26 => "lwr",
+        0x28 => "sb",
+        0x29 => "sh",
+        0x2A => "swl",
+        0x2B => "sw",
+        0x2E => "swr",
+        0x2F => "cache",
+        0x30 => "ll",
+        0x31 => "lwc1",
+        0x32 => "lwc2",
+        0x33 => "pref",
+        0x35 => "ldc1",
+        0x36 => "ldc2",
+        0x38 => "sc",
+        0x39 => "swc1",
+        0x3A => "swc2",
+        0x3D => "sdc1",
+        0x3E => "sdc2",
+        _ => return None,
+    };
+    Some(name)
+}
+
+/// returns the mnemonic of a regimm (opcode 0x01) instruction, which is
+/// picked by the rt field rather than the opcode
+pub fn regimm_name(rt: u8) -> Option<&'static str> {
+    let name = match rt {
+        0x00 => "bltz",
+        0x01 => "bgez",
+        0x02 => "bltzl",
+        0x03 => "bgezl",
+        0x10 => "bltzal",
+        0x11 => "bgezal",
+        0x12 => "bltzall",
+        0x13 => "bgezall",
+        _ => return None,
+    };
+    Some(name)
+}
+
+/// The revisions of the MIPS instruction set that `isa_level` tells apart,
+/// oldest first, each a superset of the one before; the special2 opcode
+/// (`mul`, `madd`, `clz` and the rest) isn't decoded, so `Mips32` only marks
+/// what's newer than `Mips2`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Isa {
+    Mips1,
+    Mips2,
+    Mips32,
+}
+
+impl fmt::Display for Isa {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // the same names the cli takes
+        f.pad(match self {
+            Isa::Mips1 => "mips1",
+            Isa::Mips2 => "mips2",
+            Isa::Mips32 => "mips32",
+        })
+    }
+}
+
+/// returns the oldest revision that has this instruction, or None for an
+/// invalid one; code for a revision can only use what's at or below it
+pub fn isa_level(instruction: &InsType) -> Option<Isa> {
+    let level = match *instruction {
+        InsType::Invalid(_) => return None,
+        // sync and the traps
+        InsType::RType(_, _, _, _, 0x0F) | InsType::RType(_, _, _, _, 0x30..=0x36) => Isa::Mips2,
+        // movz and movn came in with mips iv
+        InsType::RType(_, _, _, _, 0x0A | 0x0B) => Isa::Mips32,
+        // the likely forms of the regimm branches
+        InsType::IType(0x01, _, rt, _) if rt & 0x02 != 0 => Isa::Mips2,
+        // the likely branches, ll/sc and the doubleword coprocessor moves
+        InsType::IType(0x14..=0x17 | 0x30 | 0x35 | 0x36 | 0x38 | 0x3D | 0x3E, ..) => Isa::Mips2,
+        // cache (mips iii) and pref (mips iv, where mips i had lwc3)
+        InsType::IType(0x2F | 0x33, ..) => Isa::Mips32,
+        _ => Isa::Mips1,
+    };
+    Some(level)
+}
+
+/// returns the absolute address a branch or jump goes to, given the address
+/// (pc) of the instruction itself; None for anything that doesn't branch
+pub fn branch_target(instruction: &InsType, pc: u32) -> Option<u32> {
+    // everything is relative to the delay slot, the instruction after
+    let next = pc.wrapping_add(4);
+    match *instruction {
+        // regimm and the beq/bne/blez/bgtz families (and their likely forms)
+        InsType::IType(0x01, _, _, imm) | InsType::IType(0x04..=0x07, _, _, imm)
+        | InsType::IType(0x14..=0x17, _, _, imm) => {
+            // sign extend the word offset, then make it a byte offset
+            let offset = (imm as i16 as i32) << 2;
+            Some(next.wrapping_add(offset as u32))
+        }
+        // the target replaces the low 28 bits, the top 4 stay from the pc
+        InsType::JType(_, addr) => Some((next & 0xF000_0000) | (addr << 2)),
+        _ => None,
+    }
+}
+
+/// returns a register formatted the way assembly text names it, like `$sp`
+fn reg(idx: u8) -> String {
+    match REG_MAP.get(idx as usize) {
+        Some(name) => format!("${}", name),
+        // only a hand built InsType can get here, show the plain number
+        None => format!("${}", idx),
+    }
+}
+
+/// returns the assembly text of a decoded instruction, like `addi $t0, $t1, 4`
+pub fn disassemble(instruction: &InsType) -> String {
+    match *instruction {
+        InsType::RType(rs, rt, rd, shamt, func) => {
+            let name = match func_name(func) {
+                Some(name) => name,
+                None => return format!("unknown (func 0x{:X?})", func),
+            };
+            match func {
+                // shifts by a constant amount
+                0x00 | 0x02 | 0x03 => format!("{} {}, {}, {}", name, reg(rd), reg(rt), shamt),
+                // shifts by a register amount
+                0x04 | 0x06 | 0x07 => format!("{} {}, {}, {}", name, reg(rd), reg(rt), reg(rs)),
+                // jumps through a register
+                0x08 => format!("{} {}", name, reg(rs)),
+                0x09 => format!("{} {}, {}", name, reg(rd), reg(rs)),
+                // no operands at all
+                0x0C | 0x0D | 0x0F => name.to_string(),
+                // moves from or to hi/lo
+                0x10 | 0x12 => format!("{} {}", name, reg(rd)),
+                0x11 | 0x13 => format!("{} {}", name, reg(rs)),
+                // multiply, divide and traps only have two sources
+                0x18..=0x1B | 0x30..=0x36 => format!("{} {}, {}", name, reg(rs), reg(rt)),
+                // everything else is register-register: rd = rs op rt
+                _ => format!("{} {}, {}, {}", name, reg(rd), reg(rs), reg(rt)),
+            }
+        }
+        InsType::IType(0x01, rs, rt, imm) => match regimm_name(rt) {
+            // regimm branches compare rs against zero
+            Some(name) => format!("{} {}, {}", name, reg(rs), imm as i16),
+            None => format!("unknown (regimm 0x{:X?})", rt),
+        },
+        InsType::IType(op, rs, rt, imm) => {
+            let name = match opcode_name(op) {
+                Some(name) => name,
+                None => return format!("unknown (opcode 0x{:X?})", op),
+            };
+            // offsets and arithmetic sign extend their immediate, so show it signed
+            let simm = imm as i16;
+            match op {
+                // branches comparing two registers
+                0x04 | 0x05 | 0x14 | 0x15 => format!("{} {}, {}, {}", name, reg(rs), reg(rt), simm),
+                // branches comparing one register against zero
+                0x06 | 0x07 | 0x16 | 0x17 => format!("{} {}, {}", name, reg(rs), simm),
+                // lui and the logical ops zero extend, so they read better as hex
+                0x0F => format!("{} {}, 0x{:x}", name, reg(rt), imm),
+                0x0C..=0x0E => format!("{} {}, {}, 0x{:x}", name, reg(rt), reg(rs), imm),
+                // floating point loads and stores move to the $f registers
+                0x31 | 0x35 | 0x39 | 0x3D => format!("{} $f{}, {}({})", name, rt, simm, reg(rs)),
+                // other coprocessor loads/stores, cache and pref have a plain number in rt
+                0x2F | 0x32 | 0x33 | 0x36 | 0x3A | 0x3E => {
+                    format!("{} {}, {}({})", name, rt, simm, reg(rs))
+                }
+                // loads and stores use an offset from a base register
+                0x20..=0x3F => format!("{} {}, {}({})", name, reg(rt), simm, reg(rs)),
+                // everything else is register-immediate: rt = rs op imm
+                _ => format!("{} {}, {}, {}", name, reg(rt), reg(rs), simm),
+            }
+        }
+        InsType::JType(op, addr) => {
+            // j and jal are the only J-types, both are always named
+            format!("{} 0x{:X?}", opcode_name(op).unwrap_or("j"), addr)
+        }
+        InsType::Coprocessor(z, rs, rt, rd, rest) => match rs {
+            // moves between a general register and a coprocessor one
+            0x00 => format!("mfc{} {}, ${}", z, reg(rt), rd),
+            0x02 => format!("cfc{} {}, ${}", z, reg(rt), rd),
+            0x04 => format!("mtc{} {}, ${}", z, reg(rt), rd),
+            0x06 => format!("ctc{} {}, ${}", z, reg(rt), rd),
+            // branch on the coprocessor condition, true when rt's low bit is set
+            0x08 => {
+                let offset = ((rd as u16) << 11) | rest;
+                let cond = if rt & 1 == 1 { "t" } else { "f" };
+                format!("bc{}{} {}", z, cond, offset as i16)
+            }
+            // the system control coprocessor's own operations
+            0x10..=0x1F if z == 0 => match rest & 0x3F {
+                0x01 => "tlbr".to_string(),
+                0x02 => "tlbwi".to_string(),
+                0x06 => "tlbwr".to_string(),
+                0x08 => "tlbp".to_string(),
+                0x10 => "rfe".to_string(),
+                0x18 => "eret".to_string(),
+                func => format!("cop0 (func 0x{:X?})", func),
+            },
+            // anything else (floating point math and so on) shows the raw fields
+            _ => format!("cop{} 0x{:X?}, {}, {}, 0x{:X?}", z, rs, rt, rd, rest),
+        },
+        // the raw data, the way an assembler would write it back out
+        InsType::Invalid(word) => format!(".word 0x{:08X}", word),
+    }
+}
+
+/// returns the pseudo-instruction an assembler would have written for some
+/// common idioms, like `move` for `addu $t0, $t1, $zero`, or None otherwise
+pub fn pseudo(instruction: &InsType) -> Option<String> {
+    let text = match *instruction {
+        InsType::RType(0, 0, 0, 0, 0x00) => "nop".to_string(),
+        // adding or or-ing with $zero only copies the other register
+        InsType::RType(rs, 0, rd, _, 0x21) | InsType::RType(rs, 0, rd, _, 0x25) => {
+            format!("move {}, {}", reg(rd), reg(rs))
+        }
+        InsType::RType(0, rt, rd, _, 0x21) | InsType::RType(0, rt, rd, _, 0x25) => {
+            format!("move {}, {}", reg(rd), reg(rt))
+        }
+        // branches that can't fail to be taken
+        InsType::IType(0x04, 0, 0, imm) => format!("b {}", imm as i16),
+        InsType::IType(0x01, 0, 0x01, imm) => format!("b {}", imm as i16),
+        // loading a constant into a register, from $zero
+        InsType::IType(0x09, 0, rt, imm) => format!("li {}, {}", reg(rt), imm as i16),
+        InsType::IType(0x0D, 0, rt, imm) => format!("li {}, 0x{:x}", reg(rt), imm),
+        _ => return None,
+    };
+    Some(text)
+}
+
+/// shows the instruction as assembly text, the same as `disassemble`
+impl fmt::Display for InsType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // pad, so widths like {:<24} line the text up
+        f.pad(&disassemble(self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn j_type_keeps_full_26_bit_target() {
+        // j 0x0123456 -> 000010 followed by the 26-bit target
+        let instruction: u32 = 0x0812_3456;
+        match instruction_type(instruction) {
+            InsType::JType(op, addr) => {
+                assert_eq!(op, 0x02);
+                assert_eq!(addr, instruction & 0x03FF_FFFF);
+                assert_eq!(addr, 0x0012_3456);
+            }
+            _ => panic!("expected a J-type instruction"),
+        }
+    }
+
+    #[test]
+    fn add_is_r_type() {
+        // add $t0, $t1, $t2
+        match instruction_type(0x012A_4020) {
+            InsType::RType(rs, rt, rd, shamt, func) => {
+                assert_eq!((rs, rt, rd, shamt, func), (9, 10, 8, 0, 0x20));
+            }
+            _ => panic!("expected an R-type instruction"),
+        }
+    }
+
+    #[test]
+    fn addi_is_i_type() {
+        // addi $t0, $t1, 4
+        match instruction_type(0x2128_0004) {
+            InsType::IType(op, rs, rt, imm) => {
+                assert_eq!((op, rs, rt, imm), (0x08, 9, 8, 4));
+            }
+            _ => panic!("expected an I-type instruction"),
+        }
+    }
+
+    #[test]
+    fn lw_is_i_type() {
+        // lw $a0, 8($sp) has bit 27 set, which used to look like a jump
+        match instruction_type(0x8FA4_0008) {
+            InsType::IType(op, rs, rt, imm) => {
+                assert_eq!((op, rs, rt, imm), (0x23, 29, 4, 8));
+            }
+            _ => panic!("expected an I-type instruction"),
+        }
+    }
+
+    #[test]
+    fn mfc0_is_coprocessor() {
+        // mfc0 $t0, $12 (the status register)
+        let mfc0 = instruction_type(0x4008_6000);
+        assert_eq!(mfc0, InsType::Coprocessor(0, 0x00, 8, 12, 0));
+        assert_eq!(disassemble(&mfc0), "mfc0 $t0, $12");
+
+        let stats = decode(&[0x4008_6000, 0x2128_0004]);
+        assert_eq!((stats.coprocessor, stats.i_type), (1, 1));
+        assert_eq!(stats.opcodes[0x10], 1);
+    }
+
+    #[test]
+    fn parses_hex_lines_from_any_reader() {
+        let input = "0x21280004\n0x8fa40008\n";
+        let instructions = parse_instructions(input.as_bytes(), false).unwrap();
+        assert_eq!(instructions, vec![0x2128_0004, 0x8FA4_0008]);
+    }
+
+    #[test]
+    fn the_hex_prefix_is_optional() {
+        let input = "deadbeef\n0X21280004\n";
+        let instructions = parse_instructions(input.as_bytes(), false).unwrap();
+        assert_eq!(instructions, vec![0xDEAD_BEEF, 0x2128_0004]);
+    }
+
+    #[test]
+    fn bad_hex_reports_the_line() {
+        let input = "0x21280004\n0xZZ280004\n";
+        let err = parse_instructions(input.as_bytes(), false).unwrap_err();
+        assert!(matches!(err, DecodeError::BadHex { line: 2, ref text } if text == "0xZZ280004"));
+        assert!(err.to_string().contains("line 2"));
+        assert!(err.to_string().contains("0xZZ280004"));
+    }
+
+    #[test]
+    fn skips_blank_lines_and_comments() {
+        let input = "# a dump\n0x21280004\n\n   \n  # lw next\n0x8fa40008\n";
+        let mut lines = read_instructions(input.as_bytes(), false);
+        let words: Vec<u32> = lines.by_ref().map(Result::unwrap).collect();
+        assert_eq!(words, vec![0x2128_0004, 0x8FA4_0008]);
+        assert_eq!(lines.stopped_at(), None);
+
+        let binary = "\n00100001001010000000000000000100\n# done\n";
+        assert_eq!(parse_instructions(binary.as_bytes(), true).unwrap(), vec![0x2128_0004]);
+    }
+
+    #[test]
+    fn splits_several_words_on_a_line() {
+        let input = "0x21280004 8fa40008,0x012a4020\n\n0x0812_456\n";
+        let mut words = read_instructions(input.as_bytes(), false).split_words();
+        let read: Vec<u32> = words.by_ref().take(3).map(Result::unwrap).collect();
+        assert_eq!(read, vec![0x2128_0004, 0x8FA4_0008, 0x012A_4020]);
+        // the underscore isn't a digit, so the whole line is reported
+        let err = words.next().unwrap().unwrap_err();
+        assert!(err.to_string().contains("line 3"));
+        assert!(words.next().is_none());
+    }
+
+    #[test]
+    fn reads_crlf_line_endings() {
+        let input = "0x21280004\r\n8fa40008\r\n\r\n";
+        let instructions = parse_instructions(input.as_bytes(), false).unwrap();
+        assert_eq!(instructions, vec![0x2128_0004, 0x8FA4_0008]);
+        let binary = "00100001001010000000000000000100\r\n";
+        assert_eq!(parse_instructions(binary.as_bytes(), true).unwrap(), vec![0x2128_0004]);
+    }
+
+    #[test]
+    fn trims_spaces_and_tabs_around_words() {
+        let input = "\t0x21280004  \n  8fa40008\t\r\n";
+        let instructions = parse_instructions(input.as_bytes(), false).unwrap();
+        assert_eq!(instructions, vec![0x2128_0004, 0x8FA4_0008]);
+    }
+
+    #[test]
+    fn visits_every_instruction() {
+        // add, j, jal, lw, j
+        let words = [0x012A_4020, 0x0800_0004, 0x0C00_0010, 0x8FA4_0008, 0x0800_0000];
+        let mut jumps = vec![];
+        for_each_instruction(&words, |idx, word, decoded| {
+            if let InsType::JType(..) = decoded {
+                jumps.push((idx, word));
+            }
+        });
+        assert_eq!(jumps, vec![(1, 0x0800_0004), (2, 0x0C00_0010), (4, 0x0800_0000)]);
+    }
+
+    #[test]
+    fn reads_decimal_words() {
+        let input = "556269572\n2410938376\n4294967296\n";
+        let mut words = read_instructions(input.as_bytes(), false).decimal();
+        assert_eq!(words.next().unwrap().unwrap(), 0x2128_0004);
+        assert_eq!(words.next().unwrap().unwrap(), 0x8FB4_0008);
+        // one past u32::MAX
+        let err = words.next().unwrap().unwrap_err();
+        assert!(matches!(err, DecodeError::BadHex { line: 3, .. }));
+        assert!(words.next().is_none());
+    }
+
+    #[test]
+    fn skips_a_byte_order_mark() {
+        let input = "\u{feff}0x21280004\n8fa40008\n";
+        let instructions = parse_instructions(input.as_bytes(), false).unwrap();
+        assert_eq!(instructions, vec![0x2128_0004, 0x8FA4_0008]);
+    }
+
+    #[test]
+    fn streaming_matches_decode() {
+        let input = "0x21280004\n0x8fa40008\n0x012a4020\n";
+        let streamed = decode_stream(read_instructions(input.as_bytes(), false)).unwrap();
+        assert_eq!(streamed, decode(&[0x2128_0004, 0x8FA4_0008, 0x012A_4020]));
+    }
+
+    #[test]
+    fn reads_raw_words_in_either_byte_order() {
+        let bytes = [0x21, 0x28, 0x00, 0x04];
+        let big: Vec<u32> = read_raw(&bytes[..], true).map(Result::unwrap).collect();
+        let little: Vec<u32> = read_raw(&bytes[..], false).map(Result::unwrap).collect();
+        assert_eq!(big, vec![0x2128_0004]);
+        assert_eq!(little, vec![0x0400_2821]);
+
+        // 6 bytes is one word and then half of another
+        let mut words = read_raw(&[0, 0, 0, 0, 1, 2][..], true);
+        assert_eq!(words.next().unwrap().unwrap(), 0);
+        let err = words.next().unwrap().unwrap_err();
+        assert!(matches!(err, DecodeError::ShortWord { bytes: 2 }));
+        assert!(words.next().is_none());
+    }
+
+    #[test]
+    fn decodes_words_from_bytes() {
+        let bytes = [0x21, 0x28, 0x00, 0x04, 0x8F, 0xA4, 0x00, 0x08];
+        assert_eq!(decode_words(&bytes, true).unwrap(), vec![0x2128_0004, 0x8FA4_0008]);
+        assert_eq!(decode_words(&bytes, false).unwrap(), vec![0x0400_2821, 0x0800_A48F]);
+        assert_eq!(decode_words(&[], true).unwrap(), vec![]);
+
+        let err = decode_words(&bytes[..7], true).unwrap_err();
+        assert!(matches!(err, DecodeError::ShortWord { bytes: 3 }));
+    }
+
+    #[test]
+    fn tells_a_short_line_apart_from_eof() {
+        let mut lines = read_instructions("0x21280004\n".as_bytes(), false);
+        assert_eq!(lines.by_ref().count(), 1);
+        assert_eq!(lines.stopped_at(), None);
+
+        let mut lines = read_instructions("0x21280004\n0x2128 0004\n0x21280004\n".as_bytes(), false);
+        assert_eq!(lines.by_ref().count(), 1);
+        assert_eq!(lines.stopped_at(), Some((2, "0x2128 0004")));
+    }
+
+    #[test]
+    fn decode_counts_every_instruction() {
+        // add, addi, lw, j
+        let stats = decode(&[0x012A_4020, 0x2128_0004, 0x8FA4_0008, 0x0812_3456]);
+        assert_eq!(stats.total, 4);
+        assert_eq!((stats.r_type, stats.i_type, stats.j_type), (1, 2, 1));
+        assert_eq!(stats.functions[0x20], 1);
+        assert_eq!(stats.opcodes[0x23], 1);
+        assert_eq!(stats.reg_r_type[8], 1);
+        assert_eq!(stats.reg_i_type[8], 1);
+        assert_eq!((stats.reg_r_read[9], stats.reg_r_write[8]), (1, 1));
+        // addi $t0, $t1 and lw $a0, 8($sp)
+        assert_eq!(stats.reg_pairs.len(), 2);
+        assert_eq!(stats.reg_pairs[&(29, 4)], 1);
+        assert_eq!((stats.imm_min, stats.imm_max), (4, 8));
+
+        // sll $t0, $t1, 4 and srlv $t0, $t1, $t2
+        let stats = decode(&[0x0009_4100, 0x0149_4006]);
+        assert_eq!(stats.shamts[4], 1);
+        assert_eq!(stats.shamts.iter().sum::<usize>(), 1);
+
+        // syscall, jr $ra and mult don't write their rd, which is just 0
+        let stats = decode(&[0x0000_000C, 0x03E0_0008, 0x0109_0018]);
+        assert_eq!(stats.reg_r_write, [0; REG_COUNT]);
+    }
+
+    #[test]
+    fn encode_round_trips() {
+        let words = [
+            0x012A_4020, // add
+            0x2128_0004, // addi
+            0x8FA4_0008, // lw
+            0x0BFF_FFFF, // j, the whole 26-bit target
+            0x4008_6000, // mfc0
+            0xFC00_0000, // invalid
+            0x0000_0000, // nop
+        ];
+        for word in words.iter() {
+            assert_eq!(encode(instruction_type(*word)), *word, "0x{:08X}", word);
+        }
+    }
+
+    #[test]
+    fn groups_registers_by_role() {
+        let group = |idx| REG_GROUPS[reg_group(idx)];
+        assert_eq!((group(0), group(1), group(2)), ("zero", "at", "value"));
+        assert_eq!((group(4), group(8), group(25)), ("argument", "temporary", "temporary"));
+        assert_eq!((group(16), group(27)), ("saved", "kernel"));
+        assert_eq!((group(29), group(31)), ("pointer", "return"));
+    }
+
+    #[test]
+    fn counts_what_fills_delay_slots() {
+        // beq then nop, j then addi, add then lw (no branch, no slot)
+        let words = [0x1109_FFFF, 0, 0x0812_3456, 0x2128_0004, 0x012A_4020, 0x8FA4_0008];
+        let stats = decode(&words);
+        assert_eq!(stats.delay_slots, [1, 1, 0, 0, 0]);
+        assert_eq!(stats.delay_slot_nops, 1);
+
+        // a nop left out of the counts still fills its slot
+        let mut stats = Stats {
+            exclude_nops: true,
+            ..Stats::default()
+        };
+        for word in words.iter() {
+            stats.record(*word);
+        }
+        assert_eq!((stats.delay_slots, stats.delay_slot_nops), ([1, 1, 0, 0, 0], 1));
+    }
+
+    #[test]
+    fn knows_sources_from_destinations() {
+        // add $t0, $t1, $t2 writes rd
+        assert_eq!(operand_roles(&instruction_type(0x012A_4020)), ([Some(9), Some(10)], Some(8)));
+        // lw $a0, 8($sp) and addi $t0, $t1, 4 write rt
+        assert_eq!(operand_roles(&instruction_type(0x8FA4_0008)), ([Some(29), None], Some(4)));
+        assert_eq!(operand_roles(&instruction_type(0x2128_0004)), ([Some(9), None], Some(8)));
+        // sw $a0, 8($sp) and beq $t0, $t1 only read
+        assert_eq!(operand_roles(&instruction_type(0xAFA4_0008)), ([Some(29), Some(4)], None));
+        assert_eq!(operand_roles(&instruction_type(0x1109_FFFF)), ([Some(8), Some(9)], None));
+
+        let stats = decode(&[0x012A_4020, 0x8FA4_0008]);
+        assert_eq!((stats.reg_dests[8], stats.reg_dests[4], stats.reg_sources[29]), (1, 1, 1));
+    }
+
+    #[test]
+    fn entropy_in_bits() {
+        assert_eq!(entropy(&[]), 0.0);
+        assert_eq!(entropy(&[0, 7, 0]), 0.0);
+        assert_eq!(entropy(&[5, 5]), 1.0);
+        assert_eq!(entropy(&[1, 1, 1, 1, 0]), 2.0);
+    }
+
+    #[test]
+    fn classifies_load_store_offsets() {
+        // lw $a0, 8($sp), lw $a0, -2($sp), sb $a0, 3($sp), lwl $a0, 1($sp)
+        let stats = decode(&[0x8FA4_0008, 0x8FA4_FFFE, 0xA3A4_0003, 0x8BA4_0001]);
+        assert_eq!(stats.offset_alignment, [1, 1, 2]);
+        // lwl is allowed to be unaligned, and sb is only a byte
+        assert_eq!(stats.misaligned_words, 1);
+        // addi isn't a memory access at all
+        assert_eq!(decode(&[0x2128_0003]).offset_alignment, [0, 0, 0]);
+    }
+
+    #[test]
+    fn counts_returns() {
+        // jr $ra twice, jr $t9 isn't a return
+        let stats = decode(&[0x03E0_0008, 0x0320_0008, 0x03E0_0008]);
+        assert_eq!(stats.returns, 2);
+    }
+
+    #[test]
+    fn regimm_rt_is_not_a_register() {
+        // bltz $t0 and bgezal $t0, whose rt fields are 0 and 17 ($s1)
+        let stats = decode(&[0x0500_0004, 0x0511_FFFF]);
+        assert_eq!(stats.reg_i_type[8], 2);
+        assert_eq!(stats.reg_i_type[0], 0);
+        assert_eq!(stats.reg_i_type[17], 0);
+        assert!(stats.reg_pairs.is_empty());
+    }
+
+    #[test]
+    fn averages_immediates_per_opcode() {
+        // lw at -8 and 16, ori 0xFFFF, which isn't sign extended
+        let stats = decode(&[0x8FA4_FFF8, 0x8FA4_0010, 0x3508_FFFF]);
+        assert_eq!(stats.mean_imm(0x23), Some(4.0));
+        assert_eq!(stats.mean_imm(0x0D), Some(65535.0));
+        assert_eq!(stats.mean_imm(0x08), None);
+    }
+
+    #[test]
+    fn threaded_counts_match() {
+        // branches and jumps land on the chunk edges for some thread counts,
+        // so the delay slots after them have to carry over
+        let words = [
+            0x1109_FFFF, 0x0000_0000, 0x012A_4020, 0x0800_0004, 0x8FA4_0008, 0x3508_FFFF,
+            0x0000_000C, 0x03E0_0008, 0x0000_0000, 0x2128_FFFC, 0x0510_0002,
+        ];
+        // with the words counted too, so their merging is checked
+        let counting = Stats {
+            count_words: true,
+            ..Stats::default()
+        };
+        let mut single = counting.clone();
+        for word in words.iter() {
+            single.record(*word);
+        }
+        for threads in 1..=12 {
+            let mut threaded = counting.clone();
+            threaded.record_parallel(&words, threads);
+            assert_eq!(threaded, single, "{} threads", threads);
+        }
+
+        // and merging keeps working across separate calls
+        let mut split = counting.clone();
+        split.record_parallel(&words[..4], 2);
+        split.record_parallel(&words[4..], 3);
+        assert_eq!(split, single);
+    }
+
+    #[test]
+    fn counts_syscalls_and_breaks() {
+        // syscall, break, syscall with a code in the middle bits, add
+        let stats = decode(&[0x0000_000C, 0x0000_000D, 0x0001_000C, 0x012A_4020]);
+        assert_eq!((stats.syscalls, stats.breaks), (2, 1));
+    }
+
+    #[test]
+    fn counts_distinct_words() {
+        let words = [0x2128_0004, 0, 0x2128_0004, 0, 0x8FA4_0008];
+        // only kept when asked for
+        assert!(decode(&words).word_counts.is_empty());
+
+        let mut stats = Stats {
+            count_words: true,
+            ..Stats::default()
+        };
+        for word in words.iter() {
+            stats.record(*word);
+        }
+        assert_eq!((stats.word_counts.len(), stats.total), (3, 5));
+        assert_eq!(stats.word_counts[&0x2128_0004], 2);
+        assert_eq!(stats.word_counts[&0x8FA4_0008], 1);
+    }
+
+    #[test]
+    fn counts_branch_directions() {
+        // beq back one, bne forward 3, bltz forward 0, and a j which isn't a branch
+        let stats = decode(&[0x1109_FFFF, 0x1509_0003, 0x0500_0000, 0x0812_3456]);
+        assert_eq!((stats.branches_forward, stats.branches_backward), (2, 1));
+    }
+
+    #[test]
+    fn nops_can_be_left_out() {
+        let stats = decode(&[0, 0, 0x012A_4020]);
+        assert_eq!((stats.nops, stats.total, stats.r_type), (2, 3, 3));
+        assert_eq!(stats.reg_r_type[0], 6);
+
+        let mut stats = Stats {
+            exclude_nops: true,
+            ..Stats::default()
+        };
+        for word in [0, 0, 0x012A_4020].iter() {
+            stats.record(*word);
+        }
+        assert_eq!((stats.nops, stats.total, stats.r_type), (2, 1, 1));
+        assert_eq!(stats.reg_r_type[0], 0);
+    }
+
+    #[test]
+    fn counts_the_last_opcode() {
+        // opcode 0x3F is the top slot of the 6-bit field
+        let stats = decode(&[0xFC00_0000]);
+        assert_eq!(stats.opcodes.len(), 64);
+        assert_eq!(stats.opcodes[0x3F], 1);
+    }
+
+    #[test]
+    fn flags_invalid_instructions() {
+        // opcode 0x3F, r-type func 0x01 and regimm rt 0x1F are all undefined
+        for word in [0xFC00_0000_u32, 0x0000_0001, 0x041F_0000].iter() {
+            assert_eq!(instruction_type(*word), InsType::Invalid(*word));
+        }
+        let stats = decode(&[0xFC00_0000, 0x0000_0001, 0x012A_4020]);
+        assert_eq!((stats.invalid, stats.r_type, stats.i_type), (2, 1, 0));
+    }
+
+    #[test]
+    fn resolves_branch_and_jump_targets() {
+        // beq $v0, $t0, -2 at 0x00400010 goes back to 0x0040000C
+        let beq = instruction_type(0x1048_FFFE);
+        assert_eq!(branch_target(&beq, 0x0040_0010), Some(0x0040_000C));
+        // j 0x0100004 lands on 0x00400010
+        let j = instruction_type(0x0810_0004);
+        assert_eq!(branch_target(&j, 0x0040_0000), Some(0x0040_0010));
+        // addi doesn't go anywhere
+        assert_eq!(branch_target(&instruction_type(0x2128_0004), 0), None);
+    }
+
+    #[test]
+    fn opcodes_decode_to_their_expected_class() {
+        // add, lw, j and an undefined opcode
+        let stats = decode(&[0x012A_4020, 0x8FA4_0008, 0x0812_3456, 0xFC00_0000]);
+        assert_eq!(stats.opcode_classes[0x00], [1, 0, 0, 0, 0]);
+        assert_eq!(stats.opcode_classes[0x23], [0, 1, 0, 0, 0]);
+        assert_eq!(stats.opcode_classes[0x02], [0, 0, 1, 0, 0]);
+        assert_eq!(stats.opcode_classes[0x3F], [0, 0, 0, 0, 1]);
+        assert_eq!(opcode_class(0x3F), None);
+    }
+
+    #[test]
+    fn categorizes_instructions() {
+        let name = |word: u32| CATEGORIES[category(&instruction_type(word))];
+        assert_eq!(name(0x012A_4020), "arith"); // add
+        assert_eq!(name(0x2128_0004), "arith-imm"); // addi
+        assert_eq!(name(0x8FA4_0008), "load"); // lw
+        assert_eq!(name(0xAFA4_0008), "store"); // sw
+        assert_eq!(name(0x1048_FFFE), "branch"); // beq
+        assert_eq!(name(0x03E0_0008), "jump"); // jr $ra
+        assert_eq!(name(0x0000_000C), "system"); // syscall
+    }
+
+    #[test]
+    fn names_opcodes_and_funcs() {
+        assert_eq!(opcode_name(0x23), Some("lw"));
+        assert_eq!(opcode_name(0x03), Some("jal"));
+        assert_eq!(func_name(0x20), Some("add"));
+        assert_eq!(func_name(0x08), Some("jr"));
+        // undefined encodings, and the opcodes named by another field
+        assert_eq!(opcode_name(0x00), None);
+        assert_eq!(opcode_name(0x3F), None);
+        assert_eq!(func_name(0x3F), None);
+    }
+
+    #[test]
+    fn knows_which_isa_has_an_instruction() {
+        // add, lw, j and bltzal are all in the first mips
+        for word in [0x012A_4020, 0x8FA4_0008, 0x0800_0000, 0x0410_0000] {
+            assert_eq!(isa_level(&instruction_type(word)), Some(Isa::Mips1));
+        }
+        // ll, beql, teq and bgezl came with mips ii
+        for word in [0xC000_0000, 0x5000_0000, 0x0000_0034, 0x0403_0000] {
+            assert_eq!(isa_level(&instruction_type(word)), Some(Isa::Mips2));
+        }
+        // movz and pref
+        for word in [0x0000_000A, 0xCC00_0000] {
+            assert_eq!(isa_level(&instruction_type(word)), Some(Isa::Mips32));
+        }
+        assert_eq!(isa_level(&InsType::Invalid(0xFFFF_FFFF)), None);
+    }
+
+    #[test]
+    fn disassembles_each_instruction_class() {
+        // add $t0, $t1, $t2
+        assert_eq!(disassemble(&instruction_type(0x012A_4020)), "add $t0, $t1, $t2");
+        assert_eq!(disassemble(&instruction_type(0x2128_0004)), "addi $t0, $t1, 4");
+        assert_eq!(disassemble(&instruction_type(0x8FA4_0008)), "lw $a0, 8($sp)");
+        assert_eq!(disassemble(&instruction_type(0x0812_3456)), "j 0x123456");
+    }
+
+    #[test]
+    fn out_of_range_registers_dont_panic() {
+        // the fields are u8s, so an InsType can be built with any of them
+        assert_eq!(disassemble(&InsType::RType(40, 9, 8, 0, 0x20)), "add $t0, $40, $t1");
+        let mut counts = [0; REG_COUNT];
+        tally(&mut counts, 200);
+        tally(&mut counts, 31);
+        assert_eq!(counts.iter().sum::<usize>(), 1);
+    }
+
+    #[test]
+    fn displays_as_assembly() {
+        assert_eq!(instruction_type(0x012A_4020).to_string(), "add $t0, $t1, $t2");
+        assert_eq!(instruction_type(0x8FA4_0008).to_string(), "lw $a0, 8($sp)");
+        assert_eq!(instruction_type(0x0C12_3456).to_string(), "jal 0x123456");
+        assert_eq!(instruction_type(0x4008_6000).to_string(), "mfc0 $t0, $12");
+        assert_eq!(instruction_type(0xFC00_0000).to_string(), ".word 0xFC000000");
+        assert_eq!(format!("{:>8}|", InsType::RType(0, 0, 0, 0, 0x0C)), " syscall|");
+    }
+
+    #[test]
+    fn collapses_pseudo_instructions() {
+        let pseudo_of = |word| pseudo(&instruction_type(word));
+        assert_eq!(pseudo_of(0x0000_0000).as_deref(), Some("nop"));
+        // addu $t0, $t1, $zero and or $t0, $zero, $t1
+        assert_eq!(pseudo_of(0x0120_4021).as_deref(), Some("move $t0, $t1"));
+        assert_eq!(pseudo_of(0x0009_4025).as_deref(), Some("move $t0, $t1"));
+        // beq $zero, $zero, -2
+        assert_eq!(pseudo_of(0x1000_FFFE).as_deref(), Some("b -2"));
+        // addiu $t0, $zero, -1 and ori $t0, $zero, 0xff
+        assert_eq!(pseudo_of(0x2408_FFFF).as_deref(), Some("li $t0, -1"));
+        assert_eq!(pseudo_of(0x3408_00FF).as_deref(), Some("li $t0, 0xff"));
+        // a real add has no pseudo form
+        assert_eq!(pseudo_of(0x012A_4020), None);
+    }
+
+    #[test]
+    fn disassembles_immediates_signed_or_hex() {
+        // addi $t0, $t1, -4 and lw $a0, -8($sp)
+        assert_eq!(disassemble(&instruction_type(0x2128_FFFC)), "addi $t0, $t1, -4");
+        assert_eq!(disassemble(&instruction_type(0x8FA4_FFF8)), "lw $a0, -8($sp)");
+        // a backwards branch
+        assert_eq!(disassemble(&instruction_type(0x1109_FFFF)), "beq $t0, $t1, -1");
+        // andi $t0, $t1, 0xffff and lui $at, 0x1001
+        assert_eq!(disassemble(&instruction_type(0x3128_FFFF)), "andi $t0, $t1, 0xffff");
+        assert_eq!(disassemble(&instruction_type(0x3C01_1001)), "lui $at, 0x1001");
+    }
+}
@@ -1,5 +1,148 @@
+use hw1::{
+    branch_target, encode, entropy, func_name, instruction_type, isa_level, opcode_class,
+    opcode_name, pseudo, read_instructions, read_raw, reg_group, DecodeError, InsType, Isa, Stats,
+    CATEGORIES, REG_COUNT, REG_GROUPS, REG_MAP, SHIFT_FUNCS,
+};
+use std::cell::Cell;
 use std::env;
+use std::fs::File;
 use std::io;
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+use std::rc::Rc;
+use std::process::{self, Command, ExitCode, Stdio};
+
+/// printed along with any problem with the arguments
+const USAGE: &str = "usage: hw1 [-u] [-i] [-o] [-r] [-f] [-d] [OPTIONS] [FILE]...";
+
+/// printed by -h/--help
+const HELP: &str = "\
+Prints statistics about MIPS instructions, read one per line as hex
+(like 0x21280004) from each FILE, counted all together, or from stdin
+when no FILE is given. Blank lines and lines starting with # are skipped.
+Short flags can be bundled (-iu is -i -u), and long ones can be given a
+value like --top=5 or --json=true.
+
+Reports (any combination, each printed in turn):
+  -i                   instruction types: R, I, J, coprocessor and invalid
+  -o                   i-type and j-type opcodes
+  -r                   register usage by r-type and i-type instructions, with
+                       -u also as r-type reads/writes and sources/destinations
+  --columns LIST       the columns of -r to print, in order, from total,
+                       r_type, i_type, r_read, r_write, source, dest and
+                       percent; the first is what percents and sorting go by
+  -f                   r-type function codes
+  --all                the usual three reports readably, the same as -i -o -r -u
+  --immediates         min, max, zero and negative i-type immediates
+  --imm-stats          each i-type opcode with its average immediate, signed
+                       but for andi, ori, xori and lui
+  --returns            how many jr $ra there are, roughly how many functions
+  --distinct           how many different words there are out of the total,
+                       and how often each repeats on average
+  --top-words N        the N most common whole words with their disassembly,
+                       to spot idioms like saving $ra
+  --shamt              shift amounts of sll, srl and sra
+  --categories         loads, stores, branches, arithmetic and so on
+  --entropy            shannon entropy of the opcodes, in bits, after the
+                       reports; 0 is one opcode over and over
+  --ratios             with -i, loads and stores per arithmetic, logical or
+                       shift instruction, under the instruction types
+  --branch-dir         forward and backward branches, backward ones are
+                       mostly loops
+  --alignment          load and store offsets by alignment, and word sized
+                       accesses whose offset isn't a multiple of 4
+  --delay-slots        what type of instruction follows each branch and jump
+  --reg-groups         register usage summed by abi role: arguments,
+                       temporaries, saved and so on
+  --reg-pairs          i-type (rs, rt) register pairs, busiest first
+  --rd-writes          the registers r-types write to (rd), busiest first
+  --unused-regs        the registers no r-type or i-type used at all
+  --diff               the instruction types and opcodes of two FILEs side by
+                       side, with how far the second is from the first
+  --cross-tab          every opcode by the instruction class it decoded to
+  -d, --disassemble    every instruction as MIPS assembly text, after its
+                       address with --base-addr or its index otherwise
+  --jsonl              disassemble as one json object per line, with each
+                       decoded field, the address and the text
+  --annotate           disassemble with the original hex word in front of
+                       each line instead of its index or address
+  --dedupe             with -d, print a run of the same word once, marked
+                       with how many there were like (x16)
+  --pseudo             with -d, show idioms like addu $t0, $t1, $zero as
+                       the pseudo-instructions move, nop, b and li
+  --filter-opcode=0x..
+                       with -d, only instructions with this opcode
+  --filter-func=0x..   with -d, only r-types with this func
+  --window N           the instruction types of each block of N instructions
+                       in turn, to see how the mix changes through a program
+  --isa ISA            instructions that need a newer revision than ISA,
+                       mips1 or mips2, with their addresses
+  --calls              every jal as a caller -> callee pair of addresses, the
+                       edges of a call graph; an array of them with --json
+  --syscalls           each syscall and break with its address, then how
+                       many there are of each
+  --self-loops         branches and jumps whose target is their own address,
+                       using --base-addr if it is given
+  --verify             words that don't come back the same from decode and
+                       then encode, with their addresses
+
+Input:
+  -b                   lines are binary digits instead of hex
+  --decimal            lines are decimal numbers instead of hex, like some
+                       simulators print them
+  --split-words        lines can hold several words, split up by spaces or
+                       commas
+  --raw                input is raw 4-byte words instead of text
+  --gzip               inputs are gzipped, as FILEs ending in .gz always are
+  --endian=big|little  byte order of --raw words (default big)
+  --limit N            only read the first N instructions
+  --every N            only count every Nth instruction, a quicker sampled
+                       profile of a huge trace
+  --threads N          count each input on N threads, once it has all been
+                       read, which is quicker for millions of instructions
+  --progress[=N]       print a running count to stderr every N instructions
+                       (default 100000) while reading
+  --fail-on-invalid    exit with status 1 if any word isn't a valid instruction
+  --strict             warn about non-shift r-types with a nonzero shamt,
+                       a sign the input is corrupt or misaligned
+  --exclude-nops       leave nops (0x00000000) out of every count but their own
+
+Output:
+  --output FILE        write everything to FILE instead of stdout, warnings
+                       still go to stderr
+  --count-only         only print the number of instructions read
+  -u                   human readable headers, register names and footer
+  --no-header          with -u, leave out the column headers but keep the rest
+  --precision P        print percents with P decimal places (default 2)
+  --raw-counts         leave out the percent column of every report, json
+                       too, for counts that get summed up elsewhere
+  --json               print the reports as one json object
+  --csv                print the reports as comma separated values
+  --delimit            start each report with a line like === OPCODES ===
+  --sort               busiest rows first
+  --nonzero            leave out rows with a count of zero
+  --min-count N        leave out rows with a count below N
+  --top N              only the N busiest rows
+  --relative           percents out of each report's largest count instead
+                       of the total
+  --bars               a bar after each percent, scaled to the busiest row
+  --count-hex          print counts (but not percents or json) in hex
+  --numeric-regs       with -u, name registers $0-$31 instead of $zero-$ra
+  --pct-base=total|non-r
+                       opcode percentages out of every instruction (default)
+                       or out of only the non r-types
+  --reg-pct-base=total|reg
+                       register percentages out of every instruction (default)
+                       or out of only the r-types and i-types, which have
+                       registers; -u headers say which base is used
+  --base-addr=0x...    load address of the first instruction, so -d can
+                       show where branches and jumps go
+  -h, --help           print this message";
+
+/// how many characters the longest --bars bar is
+const BAR_WIDTH: usize = 40;
+
+/// how many instructions go by between lines of --progress
+const PROGRESS_EVERY: usize = 100_000;
 
 #[derive(Debug)]
 struct ProgramConfig {
@@ -7,288 +150,2472 @@ struct ProgramConfig {
     instructions: bool,
     opcodes: bool,
     registers: bool,
+    functions: bool,
+    disassemble: bool,
+    base_addr: Option<u32>,
+    binary_input: bool,
+    raw_input: bool,
+    big_endian: bool,
+    input_paths: Vec<String>,
+    json: bool,
+    csv: bool,
+    delimit: bool,
+    sort: bool,
+    nonzero: bool,
+    top: Option<usize>,
+    limit: Option<usize>,
+    immediates: bool,
+    shamts: bool,
+    cross_tab: bool,
+    categories: bool,
+    opcode_pct_non_r: bool,
+    reg_pct_bearing: bool,
+    numeric_regs: bool,
+    count_hex: bool,
+    exclude_nops: bool,
+    verify: bool,
+    progress: Option<usize>,
+    reg_pairs: bool,
+    gzip: bool,
+    strict: bool,
+    reg_groups: bool,
+    bars: bool,
+    filter_opcode: Option<u8>,
+    filter_func: Option<u8>,
+    delay_slots: bool,
+    count_only: bool,
+    self_loops: bool,
+    output: Option<String>,
+    pseudo: bool,
+    entropy: bool,
+    min_count: Option<usize>,
+    split_words: bool,
+    relative: bool,
+    fail_on_invalid: bool,
+    alignment: bool,
+    dedupe: bool,
+    ratios: bool,
+    branch_dir: bool,
+    jsonl: bool,
+    distinct: bool,
+    returns: bool,
+    annotate: bool,
+    every: Option<usize>,
+    top_words: Option<usize>,
+    no_header: bool,
+    syscalls: bool,
+    window: Option<usize>,
+    precision: usize,
+    isa: Option<Isa>,
+    diff: bool,
+    imm_stats: bool,
+    columns: Option<Vec<String>>,
+    calls: bool,
+    raw_counts: bool,
+    rd_writes: bool,
+    decimal: bool,
+    unused_regs: bool,
+    threads: usize,
+}
+
+/// a table of statistics, built by one of the `handle_*` functions
+/// and printed by `print_report` or `print_json`
+struct Report {
+    // the json key for the whole table
+    name: &'static str,
+    // the (header, json/csv key) of every column before the percent,
+    // the first is the label
+    columns: Vec<(&'static str, &'static str)>,
+    rows: Vec<Row>,
+    // the first count of each row is printed as a percentage of this,
+    // a report of plain values rather than counts has no percent column
+    total: Option<usize>,
+    // the -u header of the percent column, which can say what it's out of
+    percent_header: &'static str,
+    // whether a table of counts prints its percent column, which --columns
+    // and --raw-counts can leave out without it becoming a plain report
+    show_percent: bool,
+}
+
+/// a single labeled line of a report
+struct Row {
+    label: String,
+    counts: Vec<usize>,
+    // an average to go with the counts, like the mean immediate of
+    // --imm-stats, which isn't a count so it can't be one of them
+    mean: Option<f64>,
+    // what the percent of this row is out of, when it isn't the report's
+    // total, like the nops that --exclude-nops left out of it
+    total: Option<usize>,
+}
+
+/// a row of a single count, the shape most reports have
+fn row(label: &str, count: usize) -> Row {
+    Row {
+        label: label.to_string(),
+        counts: vec![count],
+        mean: None,
+        total: None,
+    }
 }
 
-enum InsType {
-    // RS, RT, RD, SHAM, FUNC
-    RType(u8, u8, u8, u8, u8),
-    // OP, RS, RT, IMM
-    IType(u8, u8, u8, u16),
-    // OP, Addr
-    JType(u8, u32),
+/// passes the input through untouched while counting the bytes read,
+/// so the footer can say how much of the input was consumed
+struct CountingReader<R> {
+    inner: R,
+    // shared, since the reader itself disappears into the instruction iterator
+    bytes: Rc<Cell<usize>>,
 }
 
-fn main() {
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.bytes.set(self.bytes.get() + read);
+        Ok(read)
+    }
+}
+
+impl<R: BufRead> BufRead for CountingReader<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        // bytes only count once they are consumed, not just buffered
+        self.bytes.set(self.bytes.get() + amt);
+        self.inner.consume(amt);
+    }
+}
+
+fn main() -> ExitCode {
     // setup program run config
     let config = parse_args();
 
-    let instructions: Vec<u32> = parse_instructions()
-      .expect("Failed to convert instructions, error reading stdin");
+    // the reports only need the counts, which are kept up to date as each
+    // line is read; only the disassembly needs every instruction kept around
+    let keep_instructions = config.disassemble
+        || config.verify
+        || config.self_loops
+        || config.syscalls
+        || config.window.is_some()
+        || config.isa.is_some()
+        || config.calls;
+    let mut instructions = vec![];
+    let mut stats = Stats {
+        exclude_nops: config.exclude_nops,
+        // every different word, only for the reports that need them
+        count_words: config.distinct || config.top_words.is_some(),
+        ..Stats::default()
+    };
+    let bytes_read = Rc::new(Cell::new(0));
+    let mut input = Input {
+        config: &config,
+        bytes_read: Rc::clone(&bytes_read),
+        keep_instructions,
+        instructions: &mut instructions,
+        stats: &mut stats,
+        words_read: 0,
+        truncated: false,
+    };
 
-    if config.instructions {
-        if config.human_readable {
-            // print header
-            println!("{0: <10}{1: <10}{2: <10}", "TYPE", "COUNT", "PERCENT")
+    // with --diff the second file is counted on its own, to compare with the first
+    let (paths, other) = if config.diff {
+        (&config.input_paths[..1], Some(read_stats(&config, &config.input_paths[1])))
+    } else {
+        (&config.input_paths[..], None)
+    };
+
+    // read each file given in turn, all counted together, otherwise stdin
+    let mut per_file = vec![];
+    if paths.is_empty() && config.gzip {
+        input.read_gzip("stdin", Stdio::inherit());
+    } else if paths.is_empty() {
+        input.read("stdin", Box::new(io::stdin().lock()));
+    }
+    for path in paths {
+        let before = input.words_read;
+        input.read_path(path);
+        per_file.push((path, input.words_read - before));
+    }
+
+    let words_read = input.words_read;
+    if input.truncated {
+        eprintln!("note: input truncated at {} instructions", words_read);
+    }
+
+    // reports go to --output if it was given, stdout otherwise
+    let mut out: Box<dyn Write> = match &config.output {
+        Some(path) => match File::create(path) {
+            Ok(file) => Box::new(BufWriter::new(file)),
+            Err(err) => {
+                eprintln!("Failed to create {}: {}", path, err);
+                process::exit(1);
+            }
+        },
+        None => Box::new(io::stdout().lock()),
+    };
+    let bytes_read = bytes_read.get();
+    let written = match &other {
+        Some(other) => print_diff(&mut out, &stats, other, &config),
+        None => write_output(
+            &config,
+            &stats,
+            &instructions,
+            &per_file,
+            words_read,
+            bytes_read,
+            &mut out,
+        ),
+    }
+    .and_then(|_| out.flush());
+    match written {
+        // whatever was reading the output stopped, like `head`, which is fine
+        Err(err) if err.kind() == io::ErrorKind::BrokenPipe => {}
+        Err(err) => {
+            eprintln!("Failed to write output: {}", err);
+            return ExitCode::FAILURE;
         }
-        handle_instructions(&instructions);
+        Ok(()) => {}
     }
 
-    if config.opcodes {
-        if config.human_readable {
-            // print header
-            println!("{0: <10}{1: <10}{2: <10}", "OPCODE", "COUNT", "PERCENT")
+    if config.fail_on_invalid && stats.invalid > 0 {
+        // for use as a lint, the reports are still printed first
+        eprintln!("error: {} of the words are not valid instructions", stats.invalid);
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
+}
+
+/// prints everything asked for, the disassembly and checks first and then
+/// each report in the chosen format
+fn write_output(
+    config: &ProgramConfig,
+    stats: &Stats,
+    instructions: &[u32],
+    per_file: &[(&String, usize)],
+    words_read: usize,
+    bytes_read: usize,
+    out: &mut dyn Write,
+) -> io::Result<()> {
+    if config.count_only {
+        // just the number parsed, for shell pipelines; nothing else is
+        // printed, and --exclude-nops or --every don't change it
+        return writeln!(out, "{}", words_read);
+    }
+
+    // values that go in the json object next to the reports, already
+    // written as json
+    let mut extras: Vec<(&str, String)> = vec![];
+
+    if config.disassemble {
+        if config.delimit {
+            print_delimiter(out, "disassembly")?;
+        }
+        // one line of assembly per instruction, in the order they were read
+        for (idx, instruction) in instructions.iter().enumerate() {
+            if !matches_filters(*instruction, config) {
+                continue;
+            }
+            // with --dedupe a run of the same word is one line, at its start
+            if config.dedupe && idx > 0 && instructions[idx - 1] == *instruction {
+                continue;
+            }
+            let decoded = instruction_type(*instruction);
+            let mut text = match pseudo(&decoded) {
+                Some(text) if config.pseudo => text,
+                _ => decoded.to_string(),
+            };
+            let repeats = if config.dedupe {
+                instructions[idx..].iter().take_while(|word| *word == instruction).count()
+            } else {
+                1
+            };
+
+            // with a load address, branches and jumps can show where they go
+            let pc = config.base_addr.map(|_| address_of(idx, config));
+
+            if config.jsonl {
+                writeln!(out, "{}", jsonl_line(idx, pc, &decoded, &text, repeats))?;
+                continue;
+            }
+            if repeats > 1 {
+                text.push_str(&format!(" (x{})", repeats));
+            }
+            // each line starts with where it is, so it lines up with a memory map,
+            // or with --annotate the word itself, for checking a dump by eye
+            let prefix = match pc {
+                _ if config.annotate => format!("0x{:08x} ", instruction),
+                Some(pc) => format!("0x{:08X}:", pc),
+                None => format!("{}:", idx),
+            };
+            match pc.and_then(|pc| branch_target(&decoded, pc)) {
+                Some(target) => writeln!(out, "{} {}  # 0x{:08X}", prefix, text, target)?,
+                None => writeln!(out, "{} {}", prefix, text)?,
+            }
         }
-        handle_opcodes(&instructions);
+    }
+
+    if config.verify {
+        let mismatches = verify(instructions, config);
+        if config.json {
+            extras.push(("round_trip_failures", mismatches.len().to_string()));
+            let mismatches = mismatches
+                .iter()
+                .map(|(addr, read, encoded)| {
+                    format!(
+                        "{{\"address\": {}, \"read\": {}, \"encoded\": {}}}",
+                        addr,
+                        read,
+                        encoded
+                    )
+                })
+                .collect();
+            extras.push(("round_trip_mismatches", json_array(mismatches)));
+        } else if !config.csv {
+            if config.delimit {
+                print_delimiter(out, "verify")?;
+            }
+            for (addr, read, encoded) in &mismatches {
+                writeln!(out, "0x{:08X}: read 0x{:08X}, encoded 0x{:08X}", addr, read, encoded)?;
+            }
+            writeln!(
+                out,
+                "{} of {} words did not round-trip",
+                mismatches.len(),
+                instructions.len()
+            )?;
+        }
+    }
+
+    if config.self_loops {
+        let sites = self_loops(instructions, config);
+        if config.json {
+            let sites = sites
+                .iter()
+                .map(|(idx, pc, decoded)| {
+                    format!(
+                        "{{\"index\": {}, \"address\": {}, \"text\": \"{}\"}}",
+                        idx,
+                        pc,
+                        decoded
+                    )
+                })
+                .collect();
+            extras.push(("self_loops", json_array(sites)));
+        } else if !config.csv {
+            if config.delimit {
+                print_delimiter(out, "self-loops")?;
+            }
+            for (idx, pc, decoded) in &sites {
+                writeln!(out, "{} (0x{:08X}): {}", idx, pc, decoded)?;
+            }
+            writeln!(
+                out,
+                "{} of {} instructions jump to themselves",
+                sites.len(),
+                instructions.len()
+            )?;
+        }
+    }
+
+    if config.syscalls {
+        let sites = syscall_sites(instructions, config);
+        if config.json {
+            // next to the syscalls report, which already has that name
+            let sites = sites
+                .iter()
+                .map(|(idx, pc, decoded)| {
+                    format!(
+                        "{{\"index\": {}, \"address\": {}, \"text\": \"{}\"}}",
+                        idx,
+                        pc,
+                        decoded
+                    )
+                })
+                .collect();
+            extras.push(("syscall_sites", json_array(sites)));
+        } else if !config.csv {
+            // csv is only the tables, the counts are still in the report
+            if config.delimit {
+                print_delimiter(out, "syscalls")?;
+            }
+            for (idx, pc, decoded) in sites {
+                writeln!(out, "{} (0x{:08X}): {}", idx, pc, decoded)?;
+            }
+        }
+    }
+
+    if config.calls {
+        let edges = call_edges(instructions, config);
+        if config.json {
+            // goes in the same object as the reports, not one of its own
+            let edges = edges
+                .iter()
+                .map(|(caller, callee)| {
+                    format!("{{\"caller\": {}, \"callee\": {}}}", caller, callee)
+                })
+                .collect();
+            extras.push(("calls", json_array(edges)));
+        } else {
+            if config.delimit {
+                print_delimiter(out, "calls")?;
+            }
+            for (caller, callee) in edges {
+                writeln!(out, "0x{:08X} -> 0x{:08X}", caller, callee)?;
+            }
+        }
+    }
+
+    if let Some(isa) = config.isa {
+        let sites = isa_sites(instructions, isa, config);
+        if config.json {
+            let sites = sites
+                .iter()
+                .map(|(idx, pc, decoded, level)| {
+                    format!(
+                        "{{\"index\": {}, \"address\": {}, \"text\": \"{}\", \"level\": \"{}\"}}",
+                        idx,
+                        pc,
+                        decoded,
+                        level
+                    )
+                })
+                .collect();
+            extras.push(("isa_sites", json_array(sites)));
+        } else if !config.csv {
+            if config.delimit {
+                print_delimiter(out, "isa")?;
+            }
+            for (idx, pc, decoded, level) in &sites {
+                writeln!(out, "{} (0x{:08X}): {}  # {}", idx, pc, decoded, level)?;
+            }
+            writeln!(
+                out,
+                "{} of {} instructions need a newer isa than {}",
+                sites.len(),
+                instructions.len(),
+                isa
+            )?;
+        }
+    }
+
+    if let Some(size) = config.window {
+        let windows = window_reports(instructions, size, config);
+        if config.json {
+            // in the same object as the reports, like --calls
+            let windows = windows
+                .iter()
+                .map(|(start, end, report)| {
+                    format!(
+                        "{{\"start\": {}, \"end\": {}, \"instructions\": {}}}",
+                        start,
+                        end,
+                        json_rows(report, config.precision)
+                    )
+                })
+                .collect();
+            extras.push(("windows", json_array(windows)));
+        } else {
+            if config.delimit {
+                print_delimiter(out, "windows")?;
+            }
+            print_windows(out, &windows, config)?;
+        }
+    }
+
+    if config.unused_regs {
+        // a plain list, there's nothing to count about them
+        let regs = unused_regs(stats).into_iter().map(|idx| reg_label(idx, config));
+        if config.json {
+            let regs = regs.map(|reg| format!("\"{}\"", reg)).collect();
+            extras.push(("unused_regs", json_array(regs)));
+        } else if !config.csv {
+            if config.delimit {
+                print_delimiter(out, "unused-regs")?;
+            }
+            for reg in regs {
+                writeln!(out, "{}", reg)?;
+            }
+        }
+    }
+
+    // each requested report is built up front so they can all be
+    // printed in the same format
+    let mut reports = vec![];
+
+    if config.instructions {
+        reports.push(handle_instructions(stats));
+    }
+
+    if config.opcodes {
+        reports.push(handle_opcodes(stats, config.opcode_pct_non_r));
+    }
+
+    if config.imm_stats {
+        reports.push(handle_imm_stats(stats, config.human_readable));
     }
 
     if config.registers {
+        // pass through the config so the
+        // human register names can be printed
+        let mut report = handle_registers(stats, config);
+        if let Some(keys) = &config.columns {
+            select_columns(&mut report, keys);
+        }
+        reports.push(report);
+    }
+
+    if config.functions {
+        // the function names are only printed when human readable
+        reports.push(handle_functions(stats, config.human_readable));
+    }
+
+    if config.shamts {
+        reports.push(handle_shamts(stats));
+    }
+
+    if config.cross_tab {
+        reports.push(handle_cross_tab(stats, config.human_readable));
+    }
+
+    if config.categories {
+        reports.push(handle_categories(stats));
+    }
+
+    if config.branch_dir {
+        reports.push(handle_branch_dir(stats));
+    }
+
+    if config.alignment {
+        reports.push(handle_alignment(stats));
+    }
+
+    if config.delay_slots {
+        reports.push(handle_delay_slots(stats));
+    }
+
+    if config.reg_groups {
+        reports.push(handle_reg_groups(stats));
+    }
+
+    if config.reg_pairs {
+        reports.push(handle_reg_pairs(stats, config));
+    }
+
+    if config.rd_writes {
+        reports.push(handle_rd_writes(stats, config));
+    }
+
+    if let Some(top) = config.top_words {
+        reports.push(handle_top_words(stats, top));
+    }
+
+    // only the tables of counts get rearranged, not the plain values
+    if config.immediates {
+        reports.push(handle_immediates(stats));
+    }
+
+    if config.distinct {
+        reports.push(handle_distinct(stats));
+    }
+
+    if config.returns {
+        reports.push(handle_returns(stats));
+    }
+
+    if config.syscalls {
+        reports.push(handle_syscalls(stats));
+    }
+
+    for report in reports.iter_mut().filter(|report| report.total.is_some()) {
+        if config.relative {
+            // percents out of the busiest row instead, which shows as 100%
+            report.total = Some(report.rows.iter().map(|row| row.counts[0]).max().unwrap_or(0));
+            report.percent_header = "% MAX";
+        }
+        // the top rows only make sense once the busiest rows are first
+        if config.sort || config.top.is_some() {
+            sort_rows(report);
+        }
+        if config.nonzero {
+            report.rows.retain(|row| row.counts[0] != 0);
+        }
+        if let Some(min) = config.min_count {
+            // after sorting, so --top picks from the rows that are left
+            report.rows.retain(|row| row.counts[0] >= min);
+        }
+        if let Some(top) = config.top {
+            // has no effect when there are fewer rows than that
+            report.rows.truncate(top);
+        }
+        if config.raw_counts {
+            report.show_percent = false;
+        }
+    }
+
+    // single numbers that go after the tables, not in them
+    let mut scalars: Vec<(&str, Option<f64>)> = vec![];
+    if config.entropy {
+        // every word has an opcode, r-types included, so all of them count
+        let opcode_counts: Vec<usize> =
+            stats.opcode_classes.iter().map(|classes| classes.iter().sum()).collect();
+        scalars.push(("opcode_entropy", Some(entropy(&opcode_counts))));
+    }
+    if config.ratios && config.instructions && (config.json || config.csv) {
+        // in text it is the footer of the instruction types instead
+        scalars.push(("memory_compute_ratio", memory_compute_ratio(stats)));
+    }
+    if config.distinct {
+        // how many times each different word shows up, on average
+        let ratio = if stats.word_counts.is_empty() {
+            None
+        } else {
+            Some(stats.total as f64 / stats.word_counts.len() as f64)
+        };
+        scalars.push(("duplication_ratio", ratio));
+    }
+
+    if config.json {
+        print_json(out, &reports, &scalars, &extras, config.precision)?;
+    } else if config.csv {
+        for report in &reports {
+            if config.delimit {
+                print_delimiter(out, report.name)?;
+            }
+            print_csv(out, report, config)?;
+        }
+        if !scalars.is_empty() {
+            if config.delimit {
+                print_delimiter(out, "scalars")?;
+            }
+            writeln!(out, "name,value")?;
+            for (name, value) in &scalars {
+                writeln!(out, "{},{}", name, format_scalar(*value))?;
+            }
+        }
+    } else {
+        for report in &reports {
+            if config.delimit {
+                print_delimiter(out, report.name)?;
+            }
+            print_report(out, report, config)?;
+            if config.ratios && report.name == "instructions" {
+                let ratio = memory_compute_ratio(stats);
+                writeln!(out, "memory compute ratio: {}", format_scalar(ratio))?;
+            }
+        }
+
+        for (name, value) in &scalars {
+            if config.delimit {
+                print_delimiter(out, name)?;
+            }
+            writeln!(out, "{}: {}", name.replace('_', " "), format_scalar(*value))?;
+        }
+
         if config.human_readable {
-            // print header
-            println!(
-                "{0: <10}{1: <10}{2: <10}{3: <10}{4: <10}",
-                "REG", "USE", "R-TYPE", "I-TYPE", "PERCENT"
-            )
+            // lets the user check everything was read against the input's size
+            writeln!(
+                out,
+                "{} instructions read from {} bytes of input",
+                words_read, bytes_read
+            )?;
+            if let Some(every) = config.every.filter(|every| *every > 1) {
+                // the percents are all of this sample
+                writeln!(out, "  counted every {} instruction ({})", ordinal(every), stats.total)?;
+            }
+            if config.exclude_nops {
+                writeln!(out, "  {} nops were left out of the counts", stats.nops)?;
+            }
+            if per_file.len() > 1 {
+                for (path, count) in per_file {
+                    writeln!(out, "  {} instructions from {}", count, path)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// a number as 1st, 2nd, 3rd, 4th and so on
+fn ordinal(n: usize) -> String {
+    let suffix = match (n % 10, n % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    };
+    format!("{}{}", n, suffix)
+}
+
+/// parses the number given after a flag like `--top 5`, exiting with
+/// a message when it is missing or not a number
+fn number_arg(flag: &str, value: Option<String>) -> usize {
+    match value.as_ref().map(|value| value.parse()) {
+        Some(Ok(number)) => number,
+        _ => {
+            eprintln!("{} expects a number after it", flag);
+            process::exit(1);
+        }
+    }
+}
+
+/// parses a hex number given like `--base-addr=0x00400000`, the `0x` is
+/// optional; exits with a message when it isn't one
+fn hex_arg(flag: &str, value: &str) -> u32 {
+    let digits = value.trim_start_matches("0x").trim_start_matches("0X");
+    match u32::from_str_radix(digits, 16) {
+        Ok(number) => number,
+        Err(_) => {
+            eprintln!("Could not parse {:?} for {}, expected a hex number", value, flag);
+            process::exit(1);
+        }
+    }
+}
+
+/// parses a hex opcode or func, which only have 6 bits
+fn field_arg(flag: &str, value: &str) -> u8 {
+    let field = hex_arg(flag, value);
+    if field > 0x3F {
+        eprintln!("{} expects a 6-bit value, 0x00 to 0x3F", flag);
+        process::exit(1);
+    }
+    field as u8
+}
+
+/// where the instructions of every input end up, so several files
+/// can be read into the same counts
+struct Input<'a> {
+    config: &'a ProgramConfig,
+    bytes_read: Rc<Cell<usize>>,
+    keep_instructions: bool,
+    instructions: &'a mut Vec<u32>,
+    stats: &'a mut Stats,
+    // every word read from every input so far, for --progress and --limit
+    words_read: usize,
+    // whether there were more words than --limit, which were left unread
+    truncated: bool,
+}
+
+/// reads a whole file into counts of its own, for comparing with --diff
+fn read_stats(config: &ProgramConfig, path: &str) -> Stats {
+    let mut instructions = vec![];
+    let mut stats = Stats {
+        exclude_nops: config.exclude_nops,
+        ..Stats::default()
+    };
+    let mut input = Input {
+        config,
+        bytes_read: Rc::new(Cell::new(0)),
+        keep_instructions: false,
+        instructions: &mut instructions,
+        stats: &mut stats,
+        words_read: 0,
+        truncated: false,
+    };
+    input.read_path(path);
+    stats
+}
+
+impl Input<'_> {
+    /// opens and reads one FILE, through gzip if it is compressed
+    fn read_path(&mut self, path: &str) {
+        let file = File::open(path).unwrap_or_else(|err| {
+            eprintln!("Failed to open {}: {}", path, err);
+            process::exit(1);
+        });
+        if self.config.gzip || path.ends_with(".gz") {
+            self.read_gzip(path, Stdio::from(file));
+        } else {
+            self.read(path, Box::new(BufReader::new(file)));
+        }
+    }
+
+    /// counts up every word of one input, also collecting them if they are
+    /// needed later; exits with a message naming the input if it is bad.
+    /// returns whether it got to the end of the input, rather than stopping
+    /// at --limit or a line that isn't an instruction
+    fn read(&mut self, name: &str, input: Box<dyn BufRead>) -> bool {
+        let input = CountingReader {
+            inner: input,
+            bytes: Rc::clone(&self.bytes_read),
+        };
+
+        let read = if self.config.raw_input {
+            self.read_words(read_raw(input, self.config.big_endian))
+        } else {
+            let mut lines = read_instructions(input, self.config.binary_input);
+            if self.config.split_words {
+                lines = lines.split_words();
+            }
+            if self.config.decimal {
+                lines = lines.decimal();
+            }
+            let read = self.read_words(lines.by_ref());
+            if let Some((line_number, line)) = lines.stopped_at() {
+                // the rest of the input was never looked at, so say so
+                eprintln!(
+                    "warning: stopped reading {} at line {}, {:?} is not an instruction",
+                    name, line_number, line
+                );
+                read.map(|_| false)
+            } else {
+                read
+            }
+        };
+
+        match read {
+            Ok(finished) => finished,
+            Err(err) => {
+                // a bad line is the user's input, not a bug, so no panic here
+                eprintln!("Failed to read instructions from {}: {}", name, err);
+                match err {
+                    DecodeError::ShortWord { .. } => {
+                        // raw input is a whole number of words, so it may not be raw at all
+                        eprintln!(
+                            "note: --raw expects a multiple of 4 bytes, is this a binary dump?"
+                        );
+                    }
+                    DecodeError::BadHex { .. } if self.config.decimal => {
+                        // only digits get this far, so the number is too big
+                        eprintln!("note: a decimal word can be at most {}", u32::MAX);
+                    }
+                    _ => {}
+                }
+                process::exit(1);
+            }
+        }
+    }
+
+    /// reads a gzipped input through the system's `gzip -dc`, after which
+    /// it is just like any other input
+    fn read_gzip(&mut self, name: &str, compressed: Stdio) {
+        let mut gzip = Command::new("gzip")
+            .arg("-dc")
+            .stdin(compressed)
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap_or_else(|err| {
+                eprintln!("Failed to start gzip for {}: {}", name, err);
+                process::exit(1);
+            });
+        // always there, since stdout was piped
+        let decompressed = gzip.stdout.take().unwrap();
+        let finished = self.read(name, Box::new(BufReader::new(decompressed)));
+
+        // stopping early leaves gzip writing to a closed pipe, which it
+        // fails at; only once all of it was read does a failure mean the
+        // input was bad
+        let succeeded = gzip.wait().map(|status| status.success()).unwrap_or(false);
+        if finished && !succeeded {
+            eprintln!("Failed to decompress {}, gzip gave up on it", name);
+            process::exit(1);
         }
-        // pass through the readable setting so the
-        // human register names can be printed
-        handle_registers(&instructions, config.human_readable);
+    }
+
+    /// returns whether every word was read, which is false when --limit
+    /// stopped it with words still left
+    fn read_words(
+        &mut self,
+        mut words: impl Iterator<Item = Result<u32, DecodeError>>,
+    ) -> Result<bool, DecodeError> {
+        let mut batch = vec![];
+        let mut finished = true;
+        for word in words.by_ref() {
+            // nothing past the limit is read, counting every input so far;
+            // there is a word left over, so the input really was cut short
+            if self.config.limit.is_some_and(|limit| self.words_read >= limit) {
+                self.truncated = true;
+                finished = false;
+                break;
+            }
+            let word = word?;
+            if self.config.strict {
+                check_shamt(self.words_read, word);
+            }
+            // with --every only a sample is counted, so the percents are of
+            // the sample; the disassembly still gets every word
+            let sampled = self.config.every.is_none_or(|n| self.words_read.is_multiple_of(n));
+            self.words_read += 1;
+            if let Some(every) = self.config.progress {
+                if self.words_read.is_multiple_of(every) {
+                    // stderr, so reports and json on stdout aren't touched
+                    eprintln!("read {} instructions...", self.words_read);
+                }
+            }
+            if sampled && self.config.threads > 1 {
+                // counted a block at a time on every thread, so memory
+                // stays the same however big the input is
+                batch.push(word);
+                if batch.len() == self.config.threads * BATCH_PER_THREAD {
+                    self.stats.record_parallel(&batch, self.config.threads);
+                    batch.clear();
+                }
+            } else if sampled {
+                self.stats.record(word);
+            }
+            if self.keep_instructions {
+                self.instructions.push(word);
+            }
+        }
+        if !batch.is_empty() {
+            self.stats.record_parallel(&batch, self.config.threads);
+        }
+        Ok(finished)
     }
 }
 
+/// how many words each thread of --threads counts at a time
+const BATCH_PER_THREAD: usize = 64 * 1024;
+
+/// warns about a non-shift r-type with a nonzero shamt, which a correctly
+/// encoded one never has
+fn check_shamt(idx: usize, instruction: u32) {
+    if let InsType::RType(_, _, _, shamt, func) = instruction_type(instruction) {
+        // sync keeps its barrier type where the shamt would be
+        if shamt != 0 && !SHIFT_FUNCS.contains(&func) && func != 0x0F {
+            eprintln!(
+                "warning: instruction {} (0x{:08X}) is {} with a shamt of {}, but isn't a shift",
+                idx,
+                instruction,
+                func_name(func).unwrap_or("unknown"),
+                shamt
+            );
+        }
+    }
+}
+
+/// the long flags that read a value from the next argument, which can
+/// also be given as `--top=5`
+const VALUED_FLAGS: [&str; 11] = [
+    "--top",
+    "--limit",
+    "--min-count",
+    "--output",
+    "--every",
+    "--top-words",
+    "--window",
+    "--precision",
+    "--isa",
+    "--columns",
+    "--threads",
+];
+
+/// splits up bundled short flags (`-io` is `-i -o`) and long flags given a
+/// value (`--top=5` is `--top 5`, `--json=true` is `--json`), so parse_args
+/// only ever sees one flag at a time
+fn expand_args(args: impl Iterator<Item = String>) -> Vec<String> {
+    let mut expanded = vec![];
+    for arg in args {
+        let bundled = arg.len() > 2 && arg.starts_with('-') && !arg.starts_with("--");
+        let with_value = if arg.starts_with("--") { arg.split_once('=') } else { None };
+
+        if bundled {
+            expanded.extend(arg[1..].chars().map(|letter| format!("-{}", letter)));
+        } else if let Some((flag, value)) = with_value {
+            if VALUED_FLAGS.contains(&flag) {
+                expanded.push(flag.to_string());
+                expanded.push(value.to_string());
+            } else if value == "true" {
+                expanded.push(flag.to_string());
+            } else if value != "false" {
+                // the flags that take their value after an '=' themselves
+                expanded.push(arg.clone());
+            }
+        } else {
+            expanded.push(arg.clone());
+        }
+    }
+    expanded
+}
+
 fn parse_args() -> ProgramConfig {
+    // skip the program name, it is not an argument we care about
+    parse_args_from(env::args().skip(1))
+}
+
+/// builds the config from the arguments after the program name
+fn parse_args_from(args: impl Iterator<Item = String>) -> ProgramConfig {
     // initialize a new configuration struct with all the defaults
     let mut config = ProgramConfig {
         human_readable: false,
         instructions: false,
         opcodes: false,
         registers: false,
+        functions: false,
+        disassemble: false,
+        base_addr: None,
+        binary_input: false,
+        raw_input: false,
+        big_endian: true, // canonical MIPS byte order
+        input_paths: vec![],
+        json: false,
+        csv: false,
+        delimit: false,
+        sort: false,
+        nonzero: false,
+        top: None,
+        limit: None,
+        immediates: false,
+        shamts: false,
+        cross_tab: false,
+        categories: false,
+        opcode_pct_non_r: false,
+        reg_pct_bearing: false,
+        numeric_regs: false,
+        count_hex: false,
+        exclude_nops: false,
+        verify: false,
+        progress: None,
+        reg_pairs: false,
+        gzip: false,
+        strict: false,
+        reg_groups: false,
+        bars: false,
+        filter_opcode: None,
+        filter_func: None,
+        delay_slots: false,
+        count_only: false,
+        self_loops: false,
+        output: None,
+        pseudo: false,
+        entropy: false,
+        min_count: None,
+        split_words: false,
+        relative: false,
+        fail_on_invalid: false,
+        alignment: false,
+        dedupe: false,
+        ratios: false,
+        branch_dir: false,
+        jsonl: false,
+        distinct: false,
+        returns: false,
+        annotate: false,
+        every: None,
+        top_words: None,
+        no_header: false,
+        syscalls: false,
+        window: None,
+        precision: 2,
+        isa: None,
+        diff: false,
+        imm_stats: false,
+        columns: None,
+        calls: false,
+        raw_counts: false,
+        rd_writes: false,
+        decimal: false,
+        unused_regs: false,
+        threads: 1,
     };
 
-    for arg in env::args() {
-        // for each argument check if its one we accept
-
-        if arg == "-u" {
+    let mut args = expand_args(args).into_iter();
+    while let Some(arg) = args.next() {
+        // for each argument check if its one we accept,
+        // the report flags can be combined, each report runs in turn
+        if arg == "-h" || arg == "--help" {
+            // print before any input is read, which could otherwise hang on
+            // stdin; like the reports, a closed pipe (`| head`) is no error
+            let _ = writeln!(io::stdout(), "{}\n\n{}", USAGE, HELP);
+            process::exit(0);
+        } else if arg == "-u" {
             // turn on the human readable headers and data
             config.human_readable = true;
-        }
-
-        if arg == "-i" && !config.opcodes && !config.registers {
-            // ensure neither of the other flags have been provided yet
+        } else if arg == "--no-header" {
+            // human readable rows, without the header line over them
+            config.no_header = true;
+        } else if arg == "-i" {
             // do instruction statistics
             config.instructions = true;
-        }
-
-        if arg == "-o" && !config.instructions && !config.registers {
+        } else if arg == "-o" {
             // do opcode statistics
             config.opcodes = true;
-        }
-
-        if arg == "-r" && !config.opcodes && !config.instructions {
+        } else if arg == "-r" {
             // do register statistics
             config.registers = true;
+        } else if arg == "--all" {
+            // the instruction, opcode and register reports, human readable
+            config.instructions = true;
+            config.opcodes = true;
+            config.registers = true;
+            config.human_readable = true;
+        } else if arg == "-f" {
+            // do r-type function code statistics
+            config.functions = true;
+        } else if arg == "--top" {
+            // only the N busiest rows of each report, the next arg is N
+            config.top = Some(number_arg(&arg, args.next()));
+        } else if arg == "--raw-counts" {
+            // only the counts, no percent column
+            config.raw_counts = true;
+        } else if arg == "--precision" {
+            // decimal places of every percent, the next arg is how many
+            config.precision = number_arg(&arg, args.next());
+        } else if arg == "--window" {
+            // instruction types per block of N instructions, the next arg is N
+            config.window = Some(number_arg(&arg, args.next()).max(1));
+        } else if arg == "--top-words" {
+            // the N most common whole words, the next arg is N
+            config.top_words = Some(number_arg(&arg, args.next()));
+        } else if arg == "--min-count" {
+            // leave out rows counted fewer than N times, the next arg is N
+            config.min_count = Some(number_arg(&arg, args.next()));
+        } else if arg == "--limit" {
+            // stop reading after N instructions, the next arg is N
+            config.limit = Some(number_arg(&arg, args.next()));
+        } else if arg == "--threads" {
+            // count on N threads, the next arg is N; 0 would never count
+            config.threads = number_arg(&arg, args.next()).max(1);
+        } else if arg == "--every" {
+            // only count every Nth instruction, the next arg is N; 0 would count none
+            config.every = Some(number_arg(&arg, args.next()).max(1));
+        } else if arg == "--progress" {
+            // a running count on stderr while reading
+            config.progress = Some(PROGRESS_EVERY);
+        } else if let Some(every) = arg.strip_prefix("--progress=") {
+            // the same, but every N instructions; 0 would never print
+            config.progress = Some(number_arg("--progress=", Some(every.to_string())).max(1));
+        } else if arg == "--columns" {
+            // which columns of the register report to print, in order
+            let keys = args.next().unwrap_or_else(|| {
+                eprintln!("--columns expects a list of columns after it, like total,percent");
+                process::exit(1);
+            });
+            config.columns = Some(keys.split(',').map(str::to_string).collect());
+        } else if arg == "--imm-stats" {
+            // the average immediate of each i-type opcode
+            config.imm_stats = true;
+        } else if arg == "--immediates" {
+            // do i-type immediate value statistics
+            config.immediates = true;
+        } else if let Some(base) = arg.strip_prefix("--pct-base=") {
+            // what the opcode percentages are out of
+            config.opcode_pct_non_r = match base {
+                "total" => false,
+                "non-r" => true,
+                other => {
+                    eprintln!("Unknown percent base {:?}, expected total or non-r", other);
+                    process::exit(1);
+                }
+            };
+        } else if let Some(base) = arg.strip_prefix("--reg-pct-base=") {
+            // what the register percentages are out of
+            config.reg_pct_bearing = match base {
+                "total" => false,
+                "reg" => true,
+                other => {
+                    eprintln!("Unknown percent base {:?}, expected total or reg", other);
+                    process::exit(1);
+                }
+            };
+        } else if arg == "--shamt" {
+            // do shift amount statistics
+            config.shamts = true;
+        } else if arg == "--cross-tab" {
+            // do opcode by instruction class statistics
+            config.cross_tab = true;
+        } else if arg == "--categories" {
+            // do functional category statistics
+            config.categories = true;
+        } else if arg == "--ratios" {
+            // how memory bound, as a single number
+            config.ratios = true;
+        } else if arg == "--entropy" {
+            // how varied the opcodes are, as a single number
+            config.entropy = true;
+        } else if arg == "--returns" {
+            // count the jr $ra that end functions
+            config.returns = true;
+        } else if arg == "--distinct" {
+            // do distinct word statistics
+            config.distinct = true;
+        } else if arg == "--branch-dir" {
+            // do forward and backward branch statistics
+            config.branch_dir = true;
+        } else if arg == "--alignment" {
+            // do load and store offset alignment statistics
+            config.alignment = true;
+        } else if arg == "--delay-slots" {
+            // do branch and jump delay slot statistics
+            config.delay_slots = true;
+        } else if arg == "--reg-groups" {
+            // do register usage by abi role statistics
+            config.reg_groups = true;
+        } else if arg == "--unused-regs" {
+            // list the registers nothing used
+            config.unused_regs = true;
+        } else if arg == "--rd-writes" {
+            // do r-type destination register statistics
+            config.rd_writes = true;
+        } else if arg == "--reg-pairs" {
+            // do i-type (rs, rt) register pair statistics
+            config.reg_pairs = true;
+        } else if arg == "-d" || arg == "--disassemble" {
+            // print every instruction as assembly text
+            config.disassemble = true;
+        } else if let Some(addr) = arg.strip_prefix("--base-addr=") {
+            // the address the first instruction is loaded at
+            config.base_addr = Some(hex_arg("--base-addr=", addr));
+        } else if arg == "--jsonl" {
+            // the disassembly as one json object per line
+            config.disassemble = true;
+            config.jsonl = true;
+        } else if arg == "--annotate" {
+            // the disassembly next to the word each line came from
+            config.disassemble = true;
+            config.annotate = true;
+        } else if arg == "--dedupe" {
+            // collapse runs of the same word in the disassembly
+            config.dedupe = true;
+        } else if arg == "--pseudo" {
+            // disassemble common idioms as the pseudo-instructions they are
+            config.pseudo = true;
+        } else if let Some(op) = arg.strip_prefix("--filter-opcode=") {
+            // only disassemble instructions with this opcode
+            config.filter_opcode = Some(field_arg("--filter-opcode=", op));
+        } else if let Some(func) = arg.strip_prefix("--filter-func=") {
+            // only disassemble r-types with this func
+            config.filter_func = Some(field_arg("--filter-func=", func));
+        } else if arg == "--output" {
+            // write to a file instead of stdout, the next arg is its path
+            match args.next() {
+                Some(path) => config.output = Some(path),
+                None => {
+                    eprintln!("--output expects a file path after it");
+                    process::exit(1);
+                }
+            }
+        } else if arg == "--count-only" {
+            // print how many instructions there were and nothing else
+            config.count_only = true;
+        } else if arg == "--json" {
+            // print every report as one json object
+            config.json = true;
+        } else if arg == "--csv" {
+            // print every report as comma separated values
+            config.csv = true;
+        } else if arg == "--delimit" {
+            // mark where each report starts, for scripts reading several
+            config.delimit = true;
+        } else if arg == "--sort" {
+            // print the busiest rows of each report first
+            config.sort = true;
+        } else if arg == "--nonzero" {
+            // leave out the rows nothing was counted for
+            config.nonzero = true;
+        } else if arg == "--numeric-regs" {
+            // human readable registers are $0-$31 instead of $zero-$ra
+            config.numeric_regs = true;
+        } else if arg == "--relative" {
+            // percents out of the largest count
+            config.relative = true;
+        } else if arg == "--bars" {
+            // a bar chart alongside the percentages
+            config.bars = true;
+        } else if arg == "--count-hex" {
+            // counts print as 0x.. hex, percentages stay decimal
+            config.count_hex = true;
+        } else if arg == "--self-loops" {
+            // find the branches and jumps to their own address
+            config.self_loops = true;
+        } else if arg == "--calls" {
+            // list where every jal is and where it calls
+            config.calls = true;
+        } else if arg == "--syscalls" {
+            // find and count the syscall and break instructions
+            config.syscalls = true;
+        } else if arg == "--diff" {
+            // compare two files instead of counting them together
+            config.diff = true;
+        } else if arg == "--isa" {
+            // find the instructions newer than this revision, the next arg
+            config.isa = Some(match args.next().as_deref() {
+                Some("mips1") => Isa::Mips1,
+                Some("mips2") => Isa::Mips2,
+                // no mips32 here, its special2 instructions like mul aren't
+                // decoded, so code for it would seem to be full of invalid words
+                _ => {
+                    eprintln!("--isa expects mips1 or mips2 after it");
+                    process::exit(1);
+                }
+            });
+        } else if arg == "--verify" {
+            // check the decoder against its own encoder
+            config.verify = true;
+        } else if arg == "--fail-on-invalid" {
+            // exit with a failure if anything didn't decode
+            config.fail_on_invalid = true;
+        } else if arg == "--strict" {
+            // point out words that can't be what they decode to
+            config.strict = true;
+        } else if arg == "--exclude-nops" {
+            // nops only show up in their own count
+            config.exclude_nops = true;
+        } else if arg == "--gzip" {
+            // every input is gzipped, even stdin or without a .gz name
+            config.gzip = true;
+        } else if arg == "-b" {
+            // read each line as a base-2 string instead of hex
+            config.binary_input = true;
+        } else if arg == "--decimal" {
+            // read each word as a decimal number instead of hex
+            config.decimal = true;
+        } else if arg == "--split-words" {
+            // several words to a line
+            config.split_words = true;
+        } else if arg == "--raw" {
+            // read 4-byte words instead of lines of text
+            config.raw_input = true;
+        } else if let Some(endian) = arg.strip_prefix("--endian=") {
+            // how the bytes of a raw word are ordered
+            config.big_endian = match endian {
+                "big" => true,
+                "little" => false,
+                other => {
+                    eprintln!("Unknown endianness {:?}, expected big or little", other);
+                    process::exit(1);
+                }
+            };
+        } else if !arg.starts_with('-') {
+            // anything that isn't a flag is a path to read from
+            config.input_paths.push(arg);
+        } else {
+            // most likely a typo, which would otherwise print nothing at all
+            eprintln!("Unknown flag {}", arg);
+            eprintln!("{}\ntry --help for more", USAGE);
+            process::exit(1);
         }
     }
 
-    return config;
+    if config.diff && config.input_paths.len() != 2 {
+        eprintln!("--diff compares exactly two FILEs, not {}", config.input_paths.len());
+        process::exit(1);
+    }
+
+    config
+}
+/// builds the statistics related to instruction type usage
+fn handle_instructions(stats: &Stats) -> Report {
+    // I-Type	333		69.1%
+    // J-Type	28		5.8%
+    // R-Type	121		25.1%
+    Report {
+        name: "instructions",
+        columns: vec![("TYPE", "type"), ("COUNT", "count")],
+        rows: vec![
+            row("I-Type", stats.i_type),
+            row("J-Type", stats.j_type),
+            row("R-Type", stats.r_type),
+            row("Coproc", stats.coprocessor),
+            // words that didn't decode to any real instruction
+            row("Invalid", stats.invalid),
+            // nops are r-types too, unless --exclude-nops left them out, and
+            // then they're a percent of every word instead
+            Row {
+                total: stats.exclude_nops.then_some(stats.total + stats.nops),
+                ..row("Nop", stats.nops)
+            },
+        ],
+        total: Some(stats.total),
+        percent_header: "PERCENT",
+        show_percent: true,
+    }
 }
 
-fn parse_instructions() -> Result<Vec<u32>, io::Error> {
-    let mut input = String::new(); // mutable buffer
-    let mut instructions = vec![]; // mutable vector
-    loop {
-        // forever read in a line from stdin
-        // the '?' is a way of passing the error up to my Result return type
-        let bytes = io::stdin().read_line(&mut input)?;
+/// builds the statistics related to opcode usage, as a percentage of every
+/// instruction or of only the non r-types (which are all that have an opcode)
+fn handle_opcodes(stats: &Stats, pct_non_r: bool) -> Report {
+    let rows = stats
+        .opcodes
+        .iter()
+        .enumerate()
+        .map(|(opcode, count)| Row {
+            label: format!("0x{:X?}", opcode),
+            counts: vec![*count],
+            mean: None,
+            total: None,
+        })
+        .collect();
 
-        if bytes != 11 {
-            // if we dont get exactly what we expect, we are done
-            // EOF or bad string... we can stop here; 10 chars + nl
-            return Ok(instructions);
-        }
+    Report {
+        name: "opcodes",
+        columns: vec![("OPCODE", "opcode"), ("COUNT", "count")],
+        rows,
+        total: Some(if pct_non_r {
+            // every non r-type is counted in exactly one opcode slot
+            stats.opcodes.iter().sum()
+        } else {
+            stats.total
+        }),
+        percent_header: if pct_non_r { "% NON-R" } else { "PERCENT" },
+        show_percent: true,
+    }
+}
 
-        // 2..input.len()-1 ; Chop off '0x' and '\n'
-        let digit_str = &input.as_str()[2..input.len() - 1];
+/// builds the statistics related to r-type function code usage,
+/// as a percentage of only the r-type instructions
+fn handle_functions(stats: &Stats, human_readable: bool) -> Report {
+    let rows = stats
+        .functions
+        .iter()
+        .enumerate()
+        .map(|(func, count)| Row {
+            // undefined function codes have no name, so they stay in hex
+            label: match func_name(func as u8) {
+                Some(name) if human_readable => name.to_string(),
+                _ => format!("0x{:X?}", func),
+            },
+            counts: vec![*count],
+            mean: None,
+            total: None,
+        })
+        .collect();
 
-        // parse the string from hex into an unsigned 32 bit value
-        let instruction = u32::from_str_radix(digit_str, 16)
-        // if failure to parse, crashes with this message
-            .expect("Could not parse digits");
+    Report {
+        name: "functions",
+        columns: vec![("FUNC", "func"), ("COUNT", "count")],
+        rows,
+        total: Some(stats.r_type),
+        percent_header: "PERCENT",
+        show_percent: true,
+    }
+}
 
-        // add the instruction to the mutable vector
-        instructions.push(instruction);
+/// builds the statistics related to the 16-bit immediates of i-types
+fn handle_immediates(stats: &Stats) -> Report {
+    // the min and max are nonsense until an i-type has been seen
+    let (min, max) = if stats.i_type == 0 {
+        (0, 0)
+    } else {
+        (stats.imm_min, stats.imm_max)
+    };
 
-        // clean out buffer for the next line of text
-        input.clear();
+    Report {
+        name: "immediates",
+        columns: vec![("IMM", "imm"), ("VALUE", "value")],
+        rows: vec![
+            row("MIN", min as usize),
+            row("MAX", max as usize),
+            row("ZERO", stats.imm_zero),
+            // the top bit set means negative once sign extended
+            row("NEGATIVE", stats.imm_negative),
+        ],
+        total: None,
+        percent_header: "PERCENT",
+        show_percent: true,
     }
 }
 
-/// prints the statistics related to instruction type usage
-fn handle_instructions(instructions: &Vec<u32>) {
-    // I-Type	333		69.1%
-    // J-Type	28		5.8%
-    // R-Type	121		25.1%
+/// how many different words there were, out of all of them
+fn handle_distinct(stats: &Stats) -> Report {
+    Report {
+        name: "distinct",
+        columns: vec![("WORDS", "words"), ("VALUE", "value")],
+        rows: vec![
+            row("DISTINCT", stats.word_counts.len()),
+            row("TOTAL", stats.total),
+        ],
+        total: None,
+        percent_header: "PERCENT",
+        show_percent: true,
+    }
+}
 
-    let mut r_type = 0;
-    let mut j_type = 0;
-    let mut i_type = 0;
+/// every i-type opcode that was seen with its count and average immediate,
+/// like the typical offsets of loads next to the constants of addi
+fn handle_imm_stats(stats: &Stats, human_readable: bool) -> Report {
+    let rows = (0..0x40u8)
+        .filter_map(|op| {
+            let mean = stats.mean_imm(op)?;
+            Some(Row {
+                // regimm has no name of its own, it is picked by the rt
+                label: if human_readable {
+                    format!("0x{:X?} ({})", op, opcode_name(op).unwrap_or("regimm"))
+                } else {
+                    format!("0x{:X?}", op)
+                },
+                counts: vec![stats.opcode_classes[op as usize][1]],
+                mean: Some(mean),
+                total: None,
+            })
+        })
+        .collect();
 
-    for instruction in instructions {
-        match instruction_type(&instruction) {
-            InsType::IType(_, _, _, _) => {
-                i_type += 1;
-            },
-            InsType::RType(_, _, _, _,_) => {
-                r_type += 1;
+    Report {
+        name: "imm_stats",
+        columns: vec![("OPCODE", "opcode"), ("COUNT", "count"), ("MEAN IMM", "mean")],
+        rows,
+        // out of the i-types, the only ones with an immediate
+        total: Some(stats.i_type),
+        percent_header: "% I",
+        show_percent: true,
+    }
+}
+
+/// the points where the program calls on the os
+fn handle_syscalls(stats: &Stats) -> Report {
+    Report {
+        name: "syscalls",
+        columns: vec![("FUNC", "func"), ("COUNT", "count")],
+        rows: vec![
+            row("SYSCALL", stats.syscalls),
+            row("BREAK", stats.breaks),
+        ],
+        // out of the r-types, which both of them are
+        total: Some(stats.r_type),
+        percent_header: "% R",
+        show_percent: true,
+    }
+}
+
+/// how many functions there seem to be, going by their returns
+fn handle_returns(stats: &Stats) -> Report {
+    Report {
+        name: "returns",
+        columns: vec![("RETURNS", "returns"), ("VALUE", "value")],
+        rows: vec![
+            // each function usually has one jr $ra at its end
+            row("JR-RA", stats.returns),
+        ],
+        total: None,
+        percent_header: "PERCENT",
+        show_percent: true,
+    }
+}
+
+/// builds the statistics related to shift amounts, as a percentage
+/// of only the constant shifts (sll, srl, sra)
+fn handle_shamts(stats: &Stats) -> Report {
+    let rows = stats
+        .shamts
+        .iter()
+        .enumerate()
+        .map(|(shamt, count)| Row {
+            label: shamt.to_string(),
+            counts: vec![*count],
+            mean: None,
+            total: None,
+        })
+        .collect();
+
+    Report {
+        name: "shamts",
+        columns: vec![("SHAMT", "shamt"), ("COUNT", "count")],
+        rows,
+        total: Some(stats.shamts.iter().sum()),
+        percent_header: "PERCENT",
+        show_percent: true,
+    }
+}
+
+/// builds the statistics of every opcode against the class it decoded to,
+/// warning about any opcode whose words decoded to the wrong class
+fn handle_cross_tab(stats: &Stats, human_readable: bool) -> Report {
+    let rows = stats
+        .opcode_classes
+        .iter()
+        .enumerate()
+        .map(|(opcode, classes)| {
+            let expected = opcode_class(opcode as u8);
+            // the invalid words (the last column) are never a mismatch,
+            // that just means the opcode or func is undefined
+            let mismatches: usize = ["R", "I", "J", "C"]
+                .iter()
+                .zip(classes.iter())
+                .filter(|(class, _)| expected != Some(**class))
+                .map(|(_, count)| count)
+                .sum();
+            if mismatches > 0 && expected.is_some() {
+                eprintln!(
+                    "warning: {} instructions with opcode 0x{:X?} did not decode as {}-type",
+                    mismatches,
+                    opcode,
+                    expected.unwrap_or("-")
+                );
+            }
+
+            Row {
+                // the expected class is shown next to the opcode
+                label: if human_readable {
+                    format!("0x{:X?} ({})", opcode, expected.unwrap_or("-"))
+                } else {
+                    format!("0x{:X?}", opcode)
+                },
+                counts: vec![
+                    classes.iter().sum(),
+                    classes[0],
+                    classes[1],
+                    classes[2],
+                    classes[3],
+                    classes[4],
+                    mismatches,
+                ],
+                mean: None,
+                total: None,
+            }
+        })
+        .collect();
+
+    Report {
+        name: "cross_tab",
+        columns: vec![
+            ("OPCODE", "opcode"),
+            ("COUNT", "count"),
+            ("R-TYPE", "r_type"),
+            ("I-TYPE", "i_type"),
+            ("J-TYPE", "j_type"),
+            ("COPROC", "coproc"),
+            ("INVALID", "invalid"),
+            ("MISMATCH", "mismatch"),
+        ],
+        rows,
+        total: Some(stats.total),
+        percent_header: "PERCENT",
+        show_percent: true,
+    }
+}
+
+/// builds the statistics related to functional categories, like loads,
+/// stores and branches
+fn handle_categories(stats: &Stats) -> Report {
+    let rows = CATEGORIES
+        .iter()
+        .zip(stats.categories.iter())
+        .map(|(category, count)| Row {
+            label: category.to_string(),
+            counts: vec![*count],
+            mean: None,
+            total: None,
+        })
+        .collect();
+
+    Report {
+        name: "categories",
+        columns: vec![("CATEGORY", "category"), ("COUNT", "count")],
+        rows,
+        total: Some(stats.total),
+        percent_header: "PERCENT",
+        show_percent: true,
+    }
+}
+
+/// names a register the way the config asks for
+fn reg_label(idx: usize, config: &ProgramConfig) -> String {
+    // If statements return values, so they can be inlined like so
+    if config.human_readable && config.numeric_regs {
+        // the plain register number, with a '$'
+        format!("${}", idx)
+    } else if config.human_readable {
+        // grab the human name, format it with a '$'
+        format!("${}", REG_MAP[idx])
+    } else {
+        format!("0x{:X?}", idx)
+    }
+}
+
+/// builds the statistics related to register usage
+fn handle_registers(stats: &Stats, config: &ProgramConfig) -> Report {
+    let rows = stats
+        .reg_r_type
+        .iter() // Get an iterator from the r type counts
+        .zip(stats.reg_i_type.iter()) // zip together the i-type counts so we have nice tuples
+        .enumerate() // further pair up those pairs with their index appearance in the vector
+        // we can spread each item in the tuple out in the closure arguments
+        .map(|(idx, (r_count, i_count))| Row {
+            label: reg_label(idx, config),
+            counts: {
+                let mut counts = vec![
+                    r_count + i_count, // total count
+                    *r_count,          // all r-type usage
+                    *i_count,          // all i-type usage
+                ];
+                if config.human_readable || config.columns.is_some() {
+                    // r-type reads (rs, rt) and writes (rd) broken out
+                    counts.push(stats.reg_r_read[idx]);
+                    counts.push(stats.reg_r_write[idx]);
+                    // and the same for both types, going by each one's operands
+                    counts.push(stats.reg_sources[idx]);
+                    counts.push(stats.reg_dests[idx]);
+                }
+                counts
             },
-            InsType::JType(_, _) => {
-                j_type += 1;
-            }
-        }
-    }
-
-    println!(
-        "{0: <10}{1: <10}{2: <10}",
-        "I-Type",
-        i_type,
-        format!(
-            "{:.2}%",
-            (i_type as f32 / instructions.len() as f32) * 100.0
-        )
-    );
-    println!(
-        "{0: <10}{1: <10}{2: <10}",
-        "J-Type",
-        j_type,
-        format!(
-            "{:.2}%",
-            (j_type as f32 / instructions.len() as f32) * 100.0
-        )
-    );
-    println!(
-        "{0: <10}{1: <10}{2: <10}",
-        "R-Type",
-        r_type,
-        format!(
-            "{:.2}%",
-            (r_type as f32 / instructions.len() as f32) * 100.0
-        )
-    );
-}
-
-/// prints the statistics related to opcode usage
-fn handle_opcodes(instructions: &Vec<u32>) {
-    let mut opcode_counts = [0; 0x3F]; // 0x3F zeroes
-
-    for instruction in instructions {
-        // For each instruction get the type
-        match instruction_type(&instruction) {
-            InsType::JType(op, _) => {
-                opcode_counts[op as usize] += 1;
-            }
-            InsType::IType(op, _, _, _) => {
-                opcode_counts[op as usize] += 1;
-            }
-            _ => {}
-        }
-    }
-
-    for (opcode, count) in opcode_counts.iter().enumerate() {
-        println!(
-            "{0: <10}{1: <10}{2: <10}",
-            format!("0x{:X?}", opcode),
-            count,
-            format!(
-                "{:.2}%",
-                (*count as f32 / instructions.len() as f32) * 100.0
-            )
-        );
-    }
-}
-
-/// prints the statistics related to register usage
-fn handle_registers(instructions: &Vec<u32>, human_readable: bool) {
-    // A static array of the human names of registers, in order
-    let reg_map = [
-        "zero", "at", "v0", "v1", "a0", "a1", "a2", "a3", "t0", "t1", "t2", "t3", "t4", "t5", "t6",
-        "t7", "s0", "s1", "s2", "s3", "s4", "s5", "s6", "s7", "t8", "t9", "k0", "k1", "gp", "fp",
-        "sp", "ra",
+            mean: None,
+            total: None,
+        })
+        .collect();
+
+    let mut columns = vec![
+        ("REG", "reg"),
+        ("USE", "total"),
+        ("R-TYPE", "r_type"),
+        ("I-TYPE", "i_type"),
     ];
+    if config.human_readable || config.columns.is_some() {
+        columns.push(("R-READ", "r_read"));
+        columns.push(("R-WRITE", "r_write"));
+        columns.push(("SOURCE", "source"));
+        columns.push(("DEST", "dest"));
+    }
+
+    Report {
+        name: "registers",
+        columns,
+        rows,
+        total: Some(if config.reg_pct_bearing {
+            // jumps have no registers, so only r-types and i-types count
+            stats.r_type + stats.i_type
+        } else {
+            stats.total
+        }),
+        percent_header: if config.reg_pct_bearing { "% R+I" } else { "PERCENT" },
+        show_percent: true,
+    }
+}
+
+/// keeps only the columns with these keys, in the order given, exiting
+/// with a message for one the report doesn't have
+fn select_columns(report: &mut Report, keys: &[String]) {
+    let available: Vec<&str> = report.columns[1..].iter().map(|(_, key)| *key).collect();
+    let mut columns = vec![report.columns[0]];
+    let mut picked = vec![];
+    for key in keys {
+        if let Some(idx) = available.iter().position(|available| available == key) {
+            columns.push(report.columns[idx + 1]);
+            picked.push(idx);
+        } else if key == "percent" && report.total.is_some() {
+            // the printers put the percent wherever this column is
+            columns.push(("", "percent"));
+        } else {
+            let expected = available.join(", ");
+            eprintln!("Unknown column {:?}, expected one of {}, percent", key, expected);
+            process::exit(1);
+        }
+    }
+    if picked.is_empty() {
+        eprintln!("--columns needs a column of counts, a percent has to be of something");
+        process::exit(1);
+    }
+    // still a table of counts, so sorting and --top work on it either way
+    report.show_percent = keys.iter().any(|key| key == "percent");
+    for row in &mut report.rows {
+        row.counts = picked.iter().map(|idx| row.counts[*idx]).collect();
+    }
+    report.columns = columns;
+}
+
+/// how many branches go forward and how many go back
+fn handle_branch_dir(stats: &Stats) -> Report {
+    Report {
+        name: "branch-dir",
+        columns: vec![("DIRECTION", "direction"), ("COUNT", "count")],
+        rows: vec![
+            row("Forward", stats.branches_forward),
+            // mostly loops
+            row("Backward", stats.branches_backward),
+        ],
+        total: Some(stats.branches_forward + stats.branches_backward),
+        percent_header: "% BRANCH",
+        show_percent: true,
+    }
+}
+
+/// how the offsets of loads and stores are aligned
+fn handle_alignment(stats: &Stats) -> Report {
+    Report {
+        name: "alignment",
+        columns: vec![("OFFSET", "offset"), ("COUNT", "count")],
+        rows: vec![
+            row("Word", stats.offset_alignment[0]),
+            row("Half", stats.offset_alignment[1]),
+            row("Byte", stats.offset_alignment[2]),
+            // the word sized accesses among the last two, likely bugs
+            row("Bad-Word", stats.misaligned_words),
+        ],
+        // out of every load and store
+        total: Some(stats.offset_alignment.iter().sum()),
+        percent_header: "% MEM",
+        show_percent: true,
+    }
+}
+
+/// what the instructions right after each branch and jump were
+fn handle_delay_slots(stats: &Stats) -> Report {
+    let slots = &stats.delay_slots;
+    Report {
+        name: "delay-slots",
+        columns: vec![("SLOT", "slot"), ("COUNT", "count")],
+        // the same order as the instruction type report
+        rows: vec![
+            row("I-Type", slots[1]),
+            row("J-Type", slots[2]),
+            row("R-Type", slots[0]),
+            row("Coproc", slots[3]),
+            row("Invalid", slots[4]),
+            // nops are r-types too
+            row("Nop", stats.delay_slot_nops),
+        ],
+        // out of every delay slot
+        total: Some(slots.iter().sum()),
+        percent_header: "% SLOTS",
+        show_percent: true,
+    }
+}
 
-    let mut reg_count_r_type = [0; 32]; // 32 zeros
-    let mut reg_count_i_type = [0; 32]; // 32 zeros
+/// sums register usage over each abi role, in `REG_GROUPS` order
+fn handle_reg_groups(stats: &Stats) -> Report {
+    let mut rows: Vec<Row> = REG_GROUPS
+        .iter()
+        .map(|group| Row {
+            label: group.to_string(),
+            counts: vec![0, 0, 0],
+            mean: None,
+            total: None,
+        })
+        .collect();
+    for idx in 0..REG_COUNT {
+        let counts = &mut rows[reg_group(idx)].counts;
+        counts[0] += stats.reg_r_type[idx] + stats.reg_i_type[idx];
+        counts[1] += stats.reg_r_type[idx];
+        counts[2] += stats.reg_i_type[idx];
+    }
+
+    // out of every register use, so the groups add up to 100%
+    let total = rows.iter().map(|row| row.counts[0]).sum();
+    Report {
+        name: "reg-groups",
+        columns: vec![
+            ("GROUP", "group"),
+            ("USE", "total"),
+            ("R-TYPE", "r_type"),
+            ("I-TYPE", "i_type"),
+        ],
+        rows,
+        total: Some(total),
+        percent_header: "% USES",
+        show_percent: true,
+    }
+}
+
+fn handle_reg_pairs(stats: &Stats, config: &ProgramConfig) -> Report {
+    // a hashmap has no order, so go by register number before the counts
+    let mut pairs: Vec<(&(u8, u8), &usize)> = stats.reg_pairs.iter().collect();
+    pairs.sort();
+    let rows = pairs
+        .into_iter()
+        .map(|(&(rs, rt), &count)| Row {
+            label: format!(
+                "{}/{}",
+                reg_label(rs as usize, config),
+                reg_label(rt as usize, config)
+            ),
+            counts: vec![count],
+            mean: None,
+            total: None,
+        })
+        .collect();
+    let mut report = Report {
+        name: "reg-pairs",
+        columns: vec![("RS/RT", "pair"), ("COUNT", "count")],
+        rows,
+        // out of the i-types, the only ones counted
+        total: Some(stats.i_type),
+        percent_header: "% I",
+        show_percent: true,
+    };
+    // the top pairs are the point of this one, so it is always sorted
+    sort_rows(&mut report);
+    report
+}
+
+/// the registers that were never used, as their numbers
+fn unused_regs(stats: &Stats) -> Vec<usize> {
+    (0..REG_COUNT).filter(|idx| stats.reg_r_type[*idx] + stats.reg_i_type[*idx] == 0).collect()
+}
+
+/// which registers the r-types produce their results in
+fn handle_rd_writes(stats: &Stats, config: &ProgramConfig) -> Report {
+    let rows = stats
+        .reg_r_write
+        .iter()
+        .enumerate()
+        .map(|(idx, count)| Row {
+            label: reg_label(idx, config),
+            counts: vec![*count],
+            mean: None,
+            total: None,
+        })
+        .collect();
+    let mut report = Report {
+        name: "rd-writes",
+        columns: vec![("RD", "reg"), ("WRITES", "writes")],
+        rows,
+        // out of the r-types that really write their rd
+        total: Some(stats.reg_r_write.iter().sum()),
+        percent_header: "% R",
+        show_percent: true,
+    };
+    // a ranking is the point of this one, so it is always sorted
+    sort_rows(&mut report);
+    report
+}
+
+/// the most common whole words, each labeled with its disassembly
+fn handle_top_words(stats: &Stats, top: usize) -> Report {
+    // a hashmap has no order, so go by the word before the counts
+    let mut words: Vec<(&u32, &usize)> = stats.word_counts.iter().collect();
+    words.sort();
+    let rows = words
+        .into_iter()
+        .map(|(&word, &count)| Row {
+            label: format!("0x{:08X} {}", word, instruction_type(word)),
+            counts: vec![count],
+            mean: None,
+            total: None,
+        })
+        .collect();
+    let mut report = Report {
+        name: "top-words",
+        columns: vec![("WORD", "word"), ("COUNT", "count")],
+        rows,
+        total: Some(stats.total),
+        percent_header: "PERCENT",
+        show_percent: true,
+    };
+    sort_rows(&mut report);
+    report.rows.truncate(top);
+    report
+}
+
+/// orders the rows by descending count, rows with the same count
+/// keep their original (ascending) order since the sort is stable
+fn sort_rows(report: &mut Report) {
+    report.rows.sort_by(|a, b| b.counts[0].cmp(&a.counts[0]));
+}
+
+/// returns what percent of the total a count is
+fn percent(count: usize, total: usize) -> f32 {
+    if total == 0 {
+        // nothing was read, so avoid printing NaN (which isn't valid json)
+        return 0.0;
+    }
+    // Must cast to get floats from int division
+    (count as f32 / total as f32) * 100.0
+}
 
-    for instruction in instructions {
-        // For each instruction, obtain the InsType Enum
-        match instruction_type(instruction) {
-            // Matching on r-type means checking for rs, rt, rd
-            InsType::RType(rs, rt, rd, _, _) => {
-                reg_count_r_type[rs as usize] += 1;
-                reg_count_r_type[rt as usize] += 1;
-                reg_count_r_type[rd as usize] += 1;
+/// prints the line marking the start of a section, like `=== OPCODES ===`
+fn print_delimiter(out: &mut dyn Write, name: &str) -> io::Result<()> {
+    writeln!(out, "=== {} ===", name.to_uppercase().replace('_', " "))
+}
+
+/// formats a single derived number, which might not have been computable
+fn format_scalar(value: Option<f64>) -> String {
+    match value {
+        Some(value) => format!("{:.4}", value),
+        None => "n/a".to_string(),
+    }
+}
+
+/// (loads + stores) / (arithmetic, logical and shifts), or None without any
+/// of the latter; how memory bound a program is
+fn memory_compute_ratio(stats: &Stats) -> Option<f64> {
+    let count = |name| stats.categories[CATEGORIES.iter().position(|c| *c == name).unwrap()];
+    let memory = count("load") + count("store");
+    let compute = count("arith") + count("arith-imm") + count("shift");
+    if compute == 0 {
+        None
+    } else {
+        Some(memory as f64 / compute as f64)
+    }
+}
+
+/// formats a count in decimal, or hex with --count-hex
+fn format_count(count: usize, config: &ProgramConfig) -> String {
+    if config.count_hex {
+        format!("0x{:X}", count)
+    } else {
+        count.to_string()
+    }
+}
+
+/// one value of a row, after its label
+enum Value {
+    Count(usize),
+    Percent(f32),
+    Mean(Option<f64>),
+}
+
+/// the (header, key) of every column after the label, with the percent
+/// wherever --columns put it or otherwise last, if the report has one
+fn value_columns(report: &Report) -> Vec<(&'static str, &'static str)> {
+    let mut columns = report.columns[1..].to_vec();
+    if report.total.is_none() || !report.show_percent {
+        // --raw-counts drops the percent even where --columns put it
+        columns.retain(|(_, key)| *key != "percent");
+    } else if !columns.iter().any(|(_, key)| *key == "percent") {
+        columns.push(("", "percent"));
+    }
+    columns
+        .into_iter()
+        .map(|(header, key)| match key {
+            "percent" => (report.percent_header, key),
+            _ => (header, key),
+        })
+        .collect()
+}
+
+/// the values of a row in the order of `value_columns`
+fn row_cells(report: &Report, row: &Row) -> Vec<Value> {
+    let mut counts = row.counts.iter();
+    value_columns(report)
+        .iter()
+        .map(|(_, key)| match (*key, report.total) {
+            // the percentage is always of the first count in the row
+            ("percent", Some(total)) => {
+                Value::Percent(percent(row.counts[0], row.total.unwrap_or(total)))
             }
-            // Matching on i-type means only checking rs, rt
-            InsType::IType(_, rs, rt, _) => {
-                reg_count_i_type[rs as usize] += 1;
-                reg_count_i_type[rt as usize] += 1;
+            ("mean", _) => Value::Mean(row.mean),
+            _ => Value::Count(counts.next().copied().unwrap_or(0)),
+        })
+        .collect()
+}
+
+/// prints a report as fixed width columns, with a header if human readable
+fn print_report(out: &mut dyn Write, report: &Report, config: &ProgramConfig) -> io::Result<()> {
+    // lay every cell out first so each column can be sized to fit
+    let mut lines: Vec<Vec<String>> = Vec::new();
+    if config.human_readable && !config.no_header {
+        let mut header = vec![report.columns[0].0.to_string()];
+        header.extend(value_columns(report).iter().map(|(header, _)| header.to_string()));
+        lines.push(header);
+    }
+    // the busiest row gets the full width bar, the rest in proportion
+    let max = report.rows.iter().map(|row| row.counts[0]).max().unwrap_or(0);
+    for row in &report.rows {
+        let mut line = vec![row.label.clone()];
+        for value in row_cells(report, row) {
+            line.push(match value {
+                Value::Count(count) => format_count(count, config),
+                Value::Percent(pct) => format!("{:.p$}%", pct, p = config.precision),
+                Value::Mean(mean) => format_scalar(mean),
+            });
+        }
+        if report.total.is_some() && config.bars && max > 0 {
+            line.push("#".repeat(row.counts[0] * BAR_WIDTH / max));
+        }
+        lines.push(line);
+    }
+    print_columns(out, &lines)
+}
+
+/// prints a table of cells as fixed width columns, each as wide as it needs
+fn print_columns(out: &mut dyn Write, lines: &[Vec<String>]) -> io::Result<()> {
+    // each column is as wide as its longest cell, plus a gap of two
+    let mut widths: Vec<usize> = Vec::new();
+    for line in lines {
+        for (i, cell) in line.iter().enumerate() {
+            if i == widths.len() {
+                widths.push(0);
             }
-            // No registers were harmed in these jump instructions
-            InsType::JType(_, _) => {}
+            widths[i] = widths[i].max(cell.len());
         }
     }
 
-    let counts = reg_count_r_type
-        .iter() // Get an iterator from the r type counts
-        .zip(reg_count_i_type.iter()) // zip together the i-type counts so we have nice tuples
-        .enumerate(); // further pair up those pairs with their index appearance in the vector
-
-    for (idx, (r_count, i_count)) in counts {
-        // we can spread each item in the tuple out in the for loop declaration
-        println!(
-            "{:<10}{1: <10}{2: <10}{3: <10}{4: <10}",
-            // If statements return values, so they can be inlined like so
-            if human_readable {
-                // grab the human name, format it with a '$'
-                format!("${}", reg_map[idx])
-            } else {
-                format!("0x{:X?}", idx)
-            },
-            r_count + i_count, // total count
-            r_count,           // all r-type usage
-            i_count,           // all i-type usage
-            format!(
-                "{:.2}%",
-                // Must cast to get floats from int division
-                ((r_count + i_count) as f32 / instructions.len() as f32) * 100.0
-            )
-        );
-    }
-}
-
-/// returns the enum representation of the 32-bit mips instruction
-fn instruction_type(instruction: &u32) -> InsType {
-    // This long boolean is broken out into its own variable for readability
-    // is_j_type checks if the top 6 bits are 000010 or 000011
-    let is_j_type = (instruction & 0x08_00_00_00) == 0x08_00_00_00
-        || (instruction & 0x0C_00_00_00) == 0x0C_00_00_00;
-
-    if is_j_type {
-        return InsType::JType((instruction >> 26) as u8, (instruction & 0xFFFF) as u32);
-    } else if (instruction & 0xFC_00_00_00) == 0 {
-        // Checks that he top 6 bits are zeroes, this means R-type
-        return InsType::RType(
-            (instruction >> 21) as u8,
-            ((instruction >> 16) & 0x1F as u32) as u8,
-            ((instruction >> 11) & 0x1F as u32) as u8,
-            ((instruction >> 6) & 0x1F as u32) as u8,
-            ((instruction) & 0x3F as u32) as u8,
-        );
+    for line in lines {
+        let mut text = String::new();
+        for (cell, width) in line.iter().zip(&widths) {
+            text.push_str(&format!("{: <w$}", cell, w = width + 2));
+        }
+        writeln!(out, "{}", text.trim_end())?;
+    }
+    Ok(())
+}
+
+/// prints the instruction types and opcodes of two inputs next to each other,
+/// with the change in count and percent from the first to the second
+fn print_diff(out: &mut dyn Write, a: &Stats, b: &Stats, config: &ProgramConfig) -> io::Result<()> {
+    let pairs = [
+        (handle_instructions(a), handle_instructions(b)),
+        (
+            handle_opcodes(a, config.opcode_pct_non_r),
+            handle_opcodes(b, config.opcode_pct_non_r),
+        ),
+    ];
+    let p = config.precision;
+    for (report_a, report_b) in &pairs {
+        if config.delimit {
+            print_delimiter(out, report_a.name)?;
+        }
+        let mut lines: Vec<Vec<String>> = Vec::new();
+        if config.human_readable && !config.no_header {
+            let headers = ["A", "B", "DELTA", "% A", "% B", "% DELTA"];
+            let mut header = vec![report_a.columns[0].0.to_string()];
+            header.extend(headers.iter().map(|header| header.to_string()));
+            lines.push(header);
+        }
+        // both reports come from the same handle_* so their rows line up
+        for (row_a, row_b) in report_a.rows.iter().zip(&report_b.rows) {
+            let (count_a, count_b) = (row_a.counts[0], row_b.counts[0]);
+            if config.nonzero && count_a == 0 && count_b == 0 {
+                continue;
+            }
+            let pct_a = percent(count_a, row_a.total.or(report_a.total).unwrap_or(0));
+            let pct_b = percent(count_b, row_b.total.or(report_b.total).unwrap_or(0));
+            lines.push(vec![
+                row_a.label.clone(),
+                format_count(count_a, config),
+                format_count(count_b, config),
+                format!("{:+}", count_b as i64 - count_a as i64),
+                format!("{:.p$}%", pct_a, p = p),
+                format!("{:.p$}%", pct_b, p = p),
+                format!("{:+.p$}%", pct_b - pct_a, p = p),
+            ]);
+        }
+        print_columns(out, &lines)?;
+    }
+    Ok(())
+}
+
+/// whether an instruction has the opcode and func asked for, if any were
+fn matches_filters(instruction: u32, config: &ProgramConfig) -> bool {
+    let opcode = (instruction >> 26) as u8;
+    let func = (instruction & 0x3F) as u8;
+    // only r-types (opcode 0) have a func at all
+    let func_matches = config.filter_func.is_none_or(|want| opcode == 0 && func == want);
+    config.filter_opcode.is_none_or(|want| opcode == want) && func_matches
+}
+
+/// the address of the instruction at `idx`, from --base-addr if it was
+/// given and otherwise its byte offset in the input
+fn address_of(idx: usize, config: &ProgramConfig) -> u32 {
+    // wrapping, since past 2^30 words the addresses go round like the pc would
+    config.base_addr.unwrap_or(0).wrapping_add((idx as u32).wrapping_mul(4))
+}
+
+/// decodes and re-encodes every word, giving the address, the word read and
+/// what it encoded back to for any that come back different
+fn verify(instructions: &[u32], config: &ProgramConfig) -> Vec<(u32, u32, u32)> {
+    let mut mismatches = vec![];
+    for (idx, instruction) in instructions.iter().enumerate() {
+        let encoded = encode(instruction_type(*instruction));
+        if encoded != *instruction {
+            let addr = address_of(idx, config);
+            mismatches.push((addr, *instruction, encoded));
+        }
+    }
+    mismatches
+}
+
+/// the branches and jumps that go right back to themselves, a tight loop
+/// that is usually a halt, or a bug
+fn self_loops(instructions: &[u32], config: &ProgramConfig) -> Vec<(usize, u32, InsType)> {
+    let mut loops = vec![];
+    for (idx, instruction) in instructions.iter().enumerate() {
+        let pc = address_of(idx, config);
+        let decoded = instruction_type(*instruction);
+        if branch_target(&decoded, pc) == Some(pc) {
+            loops.push((idx, pc, decoded));
+        }
+    }
+    loops
+}
+
+/// the index, address and revision of every instruction that isn't in
+/// `isa`, which code built for it shouldn't have; invalid words aren't in
+/// any revision, so they're left out
+fn isa_sites(
+    instructions: &[u32],
+    isa: Isa,
+    config: &ProgramConfig,
+) -> Vec<(usize, u32, InsType, Isa)> {
+    let mut sites = vec![];
+    for (idx, instruction) in instructions.iter().enumerate() {
+        let decoded = instruction_type(*instruction);
+        if let Some(level) = isa_level(&decoded).filter(|level| *level > isa) {
+            let pc = address_of(idx, config);
+            sites.push((idx, pc, decoded, level));
+        }
+    }
+    sites
+}
+
+/// lists the address of every jal and the address it calls, using
+/// --base-addr if it is given
+fn call_edges(instructions: &[u32], config: &ProgramConfig) -> Vec<(u32, u32)> {
+    let mut edges = vec![];
+    for (idx, instruction) in instructions.iter().enumerate() {
+        let decoded = instruction_type(*instruction);
+        if let InsType::JType(0x03, _) = decoded {
+            let pc = address_of(idx, config);
+            if let Some(target) = branch_target(&decoded, pc) {
+                edges.push((pc, target));
+            }
+        }
+    }
+    edges
+}
+
+/// the index and address of every syscall and break, so they can be found
+/// in the input
+fn syscall_sites(instructions: &[u32], config: &ProgramConfig) -> Vec<(usize, u32, InsType)> {
+    let mut sites = vec![];
+    for (idx, instruction) in instructions.iter().enumerate() {
+        let decoded = instruction_type(*instruction);
+        if let InsType::RType(_, _, _, _, 0x0C | 0x0D) = decoded {
+            let pc = address_of(idx, config);
+            sites.push((idx, pc, decoded));
+        }
+    }
+    sites
+}
+
+/// the instruction types of each block of `size` instructions on its own,
+/// with the first and last index it covers
+fn window_reports(
+    instructions: &[u32],
+    size: usize,
+    config: &ProgramConfig,
+) -> Vec<(usize, usize, Report)> {
+    instructions
+        .chunks(size)
+        .enumerate()
+        .map(|(i, chunk)| {
+            let mut stats = Stats {
+                exclude_nops: config.exclude_nops,
+                ..Stats::default()
+            };
+            for instruction in chunk {
+                stats.record(*instruction);
+            }
+            // the last window can be short, so its range says where it really ends
+            let start = i * size;
+            (start, start + chunk.len() - 1, handle_instructions(&stats))
+        })
+        .collect()
+}
+
+/// prints each window headed by its range, or as one csv table with the
+/// range at the start of every line
+fn print_windows(
+    out: &mut dyn Write,
+    windows: &[(usize, usize, Report)],
+    config: &ProgramConfig,
+) -> io::Result<()> {
+    for (i, (start, end, report)) in windows.iter().enumerate() {
+        if config.csv {
+            if i == 0 {
+                writeln!(out, "start,end,{}", csv_header(report).join(","))?;
+            }
+            for row in &report.rows {
+                writeln!(out, "{},{},{}", start, end, csv_fields(report, row, config).join(","))?;
+            }
+        } else {
+            writeln!(out, "window {}-{}:", start, end)?;
+            print_report(out, report, config)?;
+        }
+    }
+    Ok(())
+}
+
+/// prints a report as comma separated values, always with a header line
+fn print_csv(out: &mut dyn Write, report: &Report, config: &ProgramConfig) -> io::Result<()> {
+    writeln!(out, "{}", csv_header(report).join(","))?;
+    for row in &report.rows {
+        writeln!(out, "{}", csv_fields(report, row, config).join(","))?;
+    }
+    Ok(())
+}
+
+/// the keys of a report's columns, for the first line of its csv
+fn csv_header(report: &Report) -> Vec<&'static str> {
+    let mut header = vec![report.columns[0].1];
+    header.extend(value_columns(report).iter().map(|(_, key)| *key));
+    header
+}
+
+/// one row of a report as csv fields, its label first
+fn csv_fields(report: &Report, row: &Row, config: &ProgramConfig) -> Vec<String> {
+    // disassembly in a label has commas, so quote it
+    let mut fields = if row.label.contains(',') {
+        vec![format!("\"{}\"", row.label)]
     } else {
-        // All other cases are I-Types
-        return InsType::IType(
-            (instruction >> 26) as u8,
-            ((instruction >> 21) & 0x1F as u32) as u8,
-            ((instruction >> 16) & 0x1F as u32) as u8,
-            (instruction & 0xFFFF) as u16,
-        );
+        vec![row.label.clone()]
+    };
+    for value in row_cells(report, row) {
+        fields.push(match value {
+            Value::Count(count) => format_count(count, config),
+            // a bare number, no '%', so it parses cleanly
+            Value::Percent(pct) => format!("{:.p$}", pct, p = config.precision),
+            Value::Mean(mean) => format_scalar(mean),
+        });
+    }
+    fields
+}
+
+/// one instruction as a json object on a single line, with every field of
+/// its `InsType` as a key, for --jsonl
+fn jsonl_line(
+    idx: usize,
+    pc: Option<u32>,
+    decoded: &InsType,
+    text: &str,
+    repeats: usize,
+) -> String {
+    let mut fields = vec![format!("\"index\": {}", idx)];
+    if let Some(pc) = pc {
+        fields.push(format!("\"address\": {}", pc));
+    }
+    let (class, values): (&str, Vec<(&str, u32)>) = match *decoded {
+        InsType::RType(rs, rt, rd, shamt, func) => (
+            "R",
+            vec![
+                ("rs", rs as u32),
+                ("rt", rt as u32),
+                ("rd", rd as u32),
+                ("shamt", shamt as u32),
+                ("func", func as u32),
+            ],
+        ),
+        InsType::IType(op, rs, rt, imm) => (
+            "I",
+            vec![("opcode", op as u32), ("rs", rs as u32), ("rt", rt as u32), ("imm", imm as u32)],
+        ),
+        InsType::JType(op, addr) => ("J", vec![("opcode", op as u32), ("addr", addr)]),
+        InsType::Coprocessor(z, rs, rt, rd, rest) => (
+            "C",
+            vec![
+                ("z", z as u32),
+                ("rs", rs as u32),
+                ("rt", rt as u32),
+                ("rd", rd as u32),
+                ("rest", rest as u32),
+            ],
+        ),
+        InsType::Invalid(word) => ("invalid", vec![("word", word)]),
+    };
+    fields.push(format!("\"type\": \"{}\"", class));
+    for (key, value) in values {
+        fields.push(format!("\"{}\": {}", key, value));
+    }
+    if repeats > 1 {
+        // --dedupe folded this many of the same word into this line
+        fields.push(format!("\"repeats\": {}", repeats));
+    }
+    // assembly text never has quotes or backslashes, but be safe
+    let text = text.replace('\\', "\\\\").replace('"', "\\\"");
+    fields.push(format!("\"text\": \"{}\"", text));
+    format!("{{{}}}", fields.join(", "))
+}
+
+/// turns a label like "R-Type" into a json key like "r_type"
+fn json_key(name: &str) -> String {
+    format!("\"{}\"", name.to_lowercase().replace('-', "_"))
+}
+
+/// a json array of values that are already json
+fn json_array(items: Vec<String>) -> String {
+    format!("[{}]", items.join(", "))
+}
+
+/// the rows of a report as one json object, keyed by their labels
+fn json_rows(report: &Report, precision: usize) -> String {
+    let rows: Vec<String> = report
+        .rows
+        .iter()
+        .map(|row| {
+            // pair up each value with its column, skipping the label column
+            let fields: Vec<String> = value_columns(report)
+                .iter()
+                .zip(row_cells(report, row))
+                .map(|((_, key), value)| match value {
+                    Value::Count(count) => format!("\"{}\": {}", key, count),
+                    Value::Percent(pct) => format!("\"{}\": {:.p$}", key, pct, p = precision),
+                    // json has no NaN, so a missing mean is null
+                    Value::Mean(mean) => format!(
+                        "\"{}\": {}",
+                        key,
+                        mean.map_or("null".to_string(), |mean| format!("{:.4}", mean))
+                    ),
+                })
+                .collect();
+            format!("{}: {{{}}}", json_key(&row.label), fields.join(", "))
+        })
+        .collect();
+    format!("{{{}}}", rows.join(", "))
+}
+
+/// prints all the reports together as a single json object,
+/// one key per report and one key per row inside of it
+fn print_json(
+    out: &mut dyn Write,
+    reports: &[Report],
+    scalars: &[(&str, Option<f64>)],
+    extras: &[(&str, String)],
+    precision: usize,
+) -> io::Result<()> {
+    let mut sections: Vec<String> = reports
+        .iter()
+        .map(|report| format!("{}: {}", json_key(report.name), json_rows(report, precision)))
+        .collect();
+    for (name, value) in scalars {
+        // a single number, not a table; json has no NaN so null stands in
+        let value = value.map_or("null".to_string(), |value| format!("{:.4}", value));
+        sections.push(format!("\"{}\": {}", name, value));
+    }
+    for (name, value) in extras {
+        // already json, like the edges of --calls
+        sections.push(format!("\"{}\": {}", name, value));
+    }
+
+    writeln!(out, "{{{}}}", sections.join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hw1::decode;
+
+    #[test]
+    fn selecting_columns_keeps_a_table_of_counts() {
+        let stats = decode(&[0x012A_4020, 0x8FA4_0008]);
+        let config = parse_args_from(["-r", "-u"].iter().map(|arg| arg.to_string()));
+
+        let mut report = handle_registers(&stats, &config);
+        select_columns(&mut report, &["i_type".to_string(), "total".to_string()]);
+        assert_eq!(report.columns, vec![("REG", "reg"), ("I-TYPE", "i_type"), ("USE", "total")]);
+        // $sp is only used by the lw, as its base
+        assert_eq!(report.rows[29].counts, vec![1, 1]);
+        // without a percent it is still counts, so it sorts and filters
+        assert!(report.total.is_some());
+        assert!(!report.show_percent);
+        assert_eq!(value_columns(&report), vec![("I-TYPE", "i_type"), ("USE", "total")]);
+
+        let mut report = handle_registers(&stats, &config);
+        select_columns(&mut report, &["percent".to_string(), "r_type".to_string()]);
+        assert!(report.show_percent);
+        assert_eq!(value_columns(&report), vec![("PERCENT", "percent"), ("R-TYPE", "r_type")]);
+    }
+
+    #[test]
+    fn left_out_nops_are_a_percent_of_every_word() {
+        let mut stats = Stats {
+            exclude_nops: true,
+            ..Stats::default()
+        };
+        for word in [0, 0, 0x012A_4020].iter() {
+            stats.record(*word);
+        }
+        let report = handle_instructions(&stats);
+        let percents: Vec<f32> = report
+            .rows
+            .iter()
+            .map(|row| match row_cells(&report, row)[1] {
+                Value::Percent(pct) => pct,
+                _ => panic!("no percent for {}", row.label),
+            })
+            .collect();
+        // the add is all that was counted, but the nops are 2 of the 3 words
+        assert_eq!(percents[2], 100.0);
+        assert!((percents[5] - 66.67).abs() < 0.01);
     }
 }